@@ -1,27 +1,120 @@
 use crate::api::api_error::ApiError;
+use crate::api::catalog_cache::CatalogCache;
 use crate::api::mod_info::ModInfo;
-use crate::api::mod_version::ModType;
-use crate::sha256_hasher;
-use reqwest::Client;
-use std::path::PathBuf;
+use crate::api::mod_version::{ModType, ModVersion};
+use crate::api::retry::RetryPolicy;
+use crate::config::LoaderCheck;
+use crate::{config, loader, sha256_hasher};
+use reqwest::{
+    header::{HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER},
+    Client, StatusCode,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
 use tokio::io::AsyncWriteExt;
+use tokio_util::bytes::Bytes;
 
 pub(crate) mod api_error;
+pub(crate) mod catalog_cache;
+pub(crate) mod game_version;
+pub(crate) mod loader_version;
 pub(crate) mod mod_info;
 pub(crate) mod mod_version;
+pub(crate) mod requirement;
+pub(crate) mod retry;
 
-const API_DOMAIN_URL: &str = "https://api.cvrmg.com";
 const API_VERSION: &str = "v1";
 const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 
-#[cfg(debug_assertions)]
-const MODS_JSON: &str = include_str!("mods.json");
+/// The base URL that last answered, so later requests skip mirrors known to be down.
+static ACTIVE_BASE_URL: Mutex<Option<String>> = Mutex::new(None);
 
-fn get_mods_api_url() -> String {
-    format!("{API_DOMAIN_URL}/{API_VERSION}/mods/")
+fn get_mods_api_url(base_url: &str) -> String {
+    format!("{base_url}/{API_VERSION}/mods/")
 }
 
-fn create_client() -> reqwest::Result<Client> {
+/// The configured base URL and mirrors, starting with the one that answered last.
+fn get_base_urls() -> Result<Vec<String>, ApiError> {
+    let config = &config::CONFIGURATION_INSTANCE;
+    config.check_api_urls().map_err(ApiError::InvalidConfig)?;
+    let active = ACTIVE_BASE_URL
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .clone();
+
+    Ok(base_urls(
+        active,
+        config.api_base_url(),
+        config.api_mirrors(),
+    ))
+}
+
+/// `active`, `base_url` and `mirrors` in that order, without trailing slashes, blanks and
+/// duplicates.
+fn base_urls(active: Option<String>, base_url: String, mirrors: &[String]) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    let candidates = active
+        .into_iter()
+        .chain(std::iter::once(base_url))
+        .chain(mirrors.iter().cloned());
+
+    for url in candidates {
+        let url = url.trim().trim_end_matches('/').to_string();
+        if !url.is_empty() && !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+
+    urls
+}
+
+/// Points links on the official API at `base_url`, other links are returned unchanged.
+fn rewrite_to_mirror(link: &str, base_url: &str) -> String {
+    link.strip_prefix(config::DEFAULT_API_BASE_URL)
+        .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+        .map_or_else(|| link.to_string(), |rest| format!("{base_url}{rest}"))
+}
+
+/// Runs `operation` against each base URL in turn until one of them answers, telling it
+/// whether another base URL follows.
+///
+/// Only network and HTTP failures move on to the next mirror, anything else is returned as is.
+async fn with_mirrors<T, F, Fut>(mut operation: F) -> Result<T, ApiError>
+where
+    F: FnMut(String, bool) -> Fut,
+    Fut: Future<Output = Result<T, ApiError>>,
+{
+    let base_urls = get_base_urls()?;
+    let mut last_error = None;
+
+    for (index, base_url) in base_urls.iter().enumerate() {
+        let has_fallback = index + 1 < base_urls.len();
+        match operation(base_url.clone(), has_fallback).await {
+            Ok(value) => {
+                *ACTIVE_BASE_URL
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(base_url.clone());
+                return Ok(value);
+            }
+            Err(err @ (ApiError::ReqwestError(_) | ApiError::HttpStatus { .. })) => {
+                if let Some(next) = base_urls.get(index + 1) {
+                    eprintln!("{base_url} failed ({err}), trying {next}");
+                }
+                last_error = Some(err);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Err(last_error.unwrap_or(ApiError::NoDownloadUrl))
+}
+
+pub(crate) fn create_client() -> reqwest::Result<Client> {
     Client::builder()
         .user_agent(USER_AGENT)
         /*.default_headers({
@@ -35,16 +128,177 @@ fn create_client() -> reqwest::Result<Client> {
         .build()
 }
 
-pub(crate) async fn fetch_all_mods() -> Result<Vec<ModInfo>, ApiError> {
-    if cfg!(debug_assertions) {
-        let mods: Vec<ModInfo> = serde_json::from_str(MODS_JSON)?;
-        Ok(mods)
+/// Where the mod catalog is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CatalogSource {
+    /// The API, falling back to the cached catalog when it cannot be reached.
+    Online,
+    /// Only the catalog cached by the last successful fetch.
+    Offline,
+    /// A local JSON file in the same format as the API response.
+    File(PathBuf),
+}
+
+pub(crate) async fn fetch_all_mods(source: &CatalogSource) -> Result<Vec<ModInfo>, ApiError> {
+    let cache_path = catalog_cache::cache_path()?;
+    load_catalog(source, &cache_path, fetch_mods_online(&cache_path)).await
+}
+
+/// Reads the catalog from `source` with the cache at `cache_path`. `online` fetches it from
+/// the API and only runs for `CatalogSource::Online`.
+async fn load_catalog(
+    source: &CatalogSource,
+    cache_path: &Path,
+    online: impl Future<Output = Result<Vec<ModInfo>, ApiError>>,
+) -> Result<Vec<ModInfo>, ApiError> {
+    match source {
+        CatalogSource::Online => match online.await {
+            Ok(mods) => Ok(mods),
+            Err(err @ (ApiError::ReqwestError(_) | ApiError::HttpStatus { .. })) => {
+                match cached_mods(cache_path).await {
+                    Ok(Some((mods, age))) => {
+                        eprintln!(
+                            "{err}, using the catalog cached {} minute(s) ago",
+                            age.as_secs() / 60
+                        );
+                        Ok(mods)
+                    }
+                    Ok(None) => Err(err),
+                    // The network error is what needs fixing, the cache was only the fallback
+                    Err(cache_err) => {
+                        eprintln!(
+                            "The cached catalog at {} is unreadable too: {cache_err}",
+                            cache_path.display()
+                        );
+                        Err(err)
+                    }
+                }
+            }
+            Err(err) => Err(err),
+        },
+        CatalogSource::Offline => CatalogCache::load(cache_path)
+            .await?
+            .ok_or(ApiError::CatalogNotCached)?
+            .parse_mods(),
+        CatalogSource::File(path) => {
+            let contents = tokio::fs::read_to_string(path).await?;
+            Ok(serde_json::from_str(&contents)?)
+        }
+    }
+}
+
+/// The mods in the cache at `cache_path` and how old they are, `None` if nothing was cached.
+async fn cached_mods(cache_path: &Path) -> Result<Option<(Vec<ModInfo>, Duration)>, ApiError> {
+    let Some(cache) = CatalogCache::load(cache_path).await? else {
+        return Ok(None);
+    };
+
+    Ok(Some((cache.parse_mods()?, cache.age())))
+}
+
+async fn fetch_mods_online(cache_path: &Path) -> Result<Vec<ModInfo>, ApiError> {
+    let client = &create_client()?;
+    let retry = &RetryPolicy::from_config(&config::CONFIGURATION_INSTANCE);
+    let cached = CatalogCache::load(cache_path).await.ok().flatten();
+    let cached_ref = cached.as_ref();
+
+    let fetched = with_mirrors(|base_url, has_fallback| async move {
+        retry
+            .run_with_fallback(has_fallback, || {
+                fetch_catalog_if_modified(client, &base_url, cached_ref)
+            })
+            .await
+    })
+    .await?;
+
+    update_cache(cached, fetched, cache_path).await
+}
+
+/// Caches a freshly fetched catalog, or marks the cached one current when the API answered
+/// `304 Not Modified` (`fetched` is `None`), and returns its mods.
+async fn update_cache(
+    cached: Option<CatalogCache>,
+    fetched: Option<CatalogCache>,
+    cache_path: &Path,
+) -> Result<Vec<ModInfo>, ApiError> {
+    let cache = if let Some(fetched) = fetched {
+        fetched
     } else {
-        let client = create_client()?;
-        let response = client.get(get_mods_api_url()).send().await?;
-        let mods: Vec<ModInfo> = response.json().await?;
-        Ok(mods)
+        // Not modified is only accepted when a cached catalog exists
+        let mut cached = cached.ok_or(ApiError::CatalogNotCached)?;
+        cached.touch();
+        cached
+    };
+    let mods = cache.parse_mods()?;
+
+    // Failing to cache only costs us offline support, not worth failing the command over
+    if let Err(err) = cache.save(cache_path).await {
+        eprintln!("Failed to cache the mod catalog: {err}");
+    }
+
+    Ok(mods)
+}
+
+/// Requests the catalog, conditionally when a cached copy exists.
+/// Returns `None` when the server says the cached copy is still current.
+async fn fetch_catalog_if_modified(
+    client: &Client,
+    base_url: &str,
+    cached: Option<&CatalogCache>,
+) -> Result<Option<CatalogCache>, ApiError> {
+    let mut request = client.get(get_mods_api_url(base_url));
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
     }
+
+    let response = request.send().await?;
+    if response.status() == StatusCode::NOT_MODIFIED && cached.is_some() {
+        return Ok(None);
+    }
+
+    let response = check_status(response)?;
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value: &HeaderValue| value.to_str().ok())
+            .map(ToString::to_string)
+    };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+
+    Ok(Some(CatalogCache::new(
+        response.json().await?,
+        etag,
+        last_modified,
+    )))
+}
+
+/// Turns a non-success response into `ApiError::HttpStatus`, keeping its `Retry-After`.
+fn check_status(response: reqwest::Response) -> Result<reqwest::Response, ApiError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    // Only the delay-seconds form, HTTP dates fall back to the regular backoff
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+
+    Err(ApiError::HttpStatus {
+        url: response.url().to_string(),
+        status: status.as_u16(),
+        retry_after,
+    })
 }
 
 fn get_file_name_from_url(response: &reqwest::Response) -> Option<String> {
@@ -52,22 +306,41 @@ fn get_file_name_from_url(response: &reqwest::Response) -> Option<String> {
 
     remote_url
         .split('/')
-        .filter(|s| !s.is_empty()) // Ignore empty segments
-        .last()
+        .rfind(|s| !s.is_empty()) // Ignore empty segments
         .filter(|&s| {
             std::path::Path::new(s)
                 .extension()
-                .map_or(false, |ext| ext.eq_ignore_ascii_case("dll"))
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("dll"))
         })
         .map(std::string::ToString::to_string)
 }
 
+/// Installs the latest version of `mod_info`, unless its approval status is refused by the
+/// configured install policy or it targets another `MelonLoader` and the loader check refuses.
 pub(crate) async fn download_and_verify_mod_with_info<P: Into<PathBuf>>(
+    client: &Client,
     mod_info: &ModInfo,
     loader_path: P,
-) -> Result<(), ApiError> {
-    let mod_version = &mod_info.versions[0];
+) -> Result<PathBuf, ApiError> {
+    let loader_path: PathBuf = loader_path.into();
+    let mod_version = mod_info
+        .latest_version()
+        .ok_or(ApiError::ModVersionNotFound)?;
+
+    let policy = config::CONFIGURATION_INSTANCE.install_policy();
+    if !policy.allows(&mod_version.approval_status) {
+        return Err(ApiError::RefusedByPolicy {
+            mod_name: mod_info.name.clone(),
+            status: mod_version.approval_status.to_string(),
+            policy: policy.to_string(),
+        });
+    }
+
+    check_loader_version(mod_info, mod_version, &loader_path).await?;
+
     download_and_verify_mod(
+        client,
+        mod_info.id,
         mod_version.download_link.as_str(),
         mod_version.hash.as_str(),
         &mod_version.mod_type,
@@ -76,41 +349,543 @@ pub(crate) async fn download_and_verify_mod_with_info<P: Into<PathBuf>>(
     .await
 }
 
+/// Compares the loader version `mod_version` targets with the installed one, warning or
+/// failing as configured. Passes when the installed version is unknown.
+async fn check_loader_version(
+    mod_info: &ModInfo,
+    mod_version: &ModVersion,
+    chillout_folder: &Path,
+) -> Result<(), ApiError> {
+    let check = config::CONFIGURATION_INSTANCE.loader_check();
+    if check == LoaderCheck::Ignore {
+        return Ok(());
+    }
+
+    let Some(installed) = loader::installed_loader_version(chillout_folder).await else {
+        return Ok(());
+    };
+    if mod_version.loader_version.matches(&installed) {
+        return Ok(());
+    }
+
+    let err = ApiError::IncompatibleLoader {
+        mod_name: mod_info.name.clone(),
+        required: mod_version.loader_version.to_string(),
+        installed: installed.to_string(),
+    };
+    if check == LoaderCheck::Refuse {
+        return Err(err);
+    }
+
+    eprintln!("Warning: {err}");
+    Ok(())
+}
+
 pub(crate) async fn download_and_verify_mod<P: Into<PathBuf>>(
+    client: &Client,
+    mod_id: usize,
     mod_url: &str,
     mod_hash: &str,
     mod_type: &ModType,
     loader_path: P,
-) -> Result<(), ApiError> {
-    let client = create_client()?;
-    let response = client.get(mod_url).send().await?;
+) -> Result<PathBuf, ApiError> {
+    let loader_path = &loader_path.into();
+    let retry = &RetryPolicy::from_config(&config::CONFIGURATION_INSTANCE);
 
-    // Use error_for_status to handle non-success statuses
-    if let Err(err) = response.error_for_status_ref() {
-        return Err(err.into());
+    let download = |url: String, has_fallback: bool| async move {
+        retry
+            .run_with_fallback(has_fallback, || async {
+                let response = check_status(client.get(&url).send().await?)?;
+                save_verified_mod(response, mod_id, mod_hash, mod_type, loader_path).await
+            })
+            .await
+    };
+
+    if mod_url.starts_with(config::DEFAULT_API_BASE_URL) {
+        with_mirrors(|base_url, has_fallback| {
+            download(rewrite_to_mirror(mod_url, &base_url), has_fallback)
+        })
+        .await
+    } else {
+        download(mod_url.to_string(), false).await
     }
+}
 
-    if let Some(file_name) = get_file_name_from_url(&response) {
-        let bytes = response.bytes().await?;
+/// Streams the mod in `response` into a temporary file and renames it into `Mods` or
+/// `Plugins` only if it hashes to `mod_hash`, removing the temporary file otherwise.
+async fn save_verified_mod(
+    response: reqwest::Response,
+    mod_id: usize,
+    mod_hash: &str,
+    mod_type: &ModType,
+    loader_path: &Path,
+) -> Result<PathBuf, ApiError> {
+    let Some(file_name) = get_file_name_from_url(&response) else {
+        return Err(ApiError::InvalidFileName);
+    };
+
+    let file_path = loader_path.join(mod_type.folder_name()).join(&file_name);
+    // Named after the mod too, two mods of one batch may ship files with the same name
+    let temp_path = file_path.with_file_name(format!("{file_name}.{mod_id}.part"));
 
-        let hash = sha256_hasher::compute_sha256_hash(&bytes);
-        if hash != mod_hash {
-            return Err(ApiError::InvalidFileHash);
+    // Stream into a temporary file next to the target so the rename below stays on one filesystem
+    let hash = match stream_to_file(response, &temp_path).await {
+        Ok(hash) => hash,
+        Err(err) => {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(err);
         }
+    };
 
-        let file_path = match mod_type {
-            ModType::Mod => loader_path.into().join("Mods"),
-            ModType::Plugin => loader_path.into().join("Plugins"),
-        };
+    if hash != mod_hash {
+        tokio::fs::remove_file(&temp_path).await?;
+        return Err(ApiError::InvalidFileHash);
+    }
 
-        let file_path = file_path.join(file_name);
-        let mut file = crate::utils::create_file_with_directories(&file_path).await?;
+    tokio::fs::rename(&temp_path, &file_path).await?;
+    Ok(file_path)
+}
 
-        file.write_all(&bytes).await?;
-        file.flush().await?;
+/// Downloads `url` into memory, retried like mod downloads, for archives that are
+/// extracted right away.
+pub(crate) async fn download_bytes(client: &Client, url: &str) -> Result<Bytes, ApiError> {
+    let retry = RetryPolicy::from_config(&config::CONFIGURATION_INSTANCE);
 
-        Ok(())
-    } else {
-        Err(ApiError::InvalidFileName)
+    retry
+        .run(|| async {
+            let response = check_status(client.get(url).send().await?)?;
+            Ok(response.bytes().await?)
+        })
+        .await
+}
+
+/// Writes the response body to `path` chunk by chunk, returning its base64 SHA-256 hash.
+async fn stream_to_file(mut response: reqwest::Response, path: &Path) -> Result<String, ApiError> {
+    let mut file = crate::utils::create_file_with_directories(path).await?;
+    let mut hasher = Sha256::new();
+
+    while let Some(chunk) = response.chunk().await? {
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+
+    file.flush().await?;
+    file.sync_all().await?;
+
+    Ok(sha256_hasher::encode_hash(hasher))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        base_urls, fetch_catalog_if_modified, load_catalog, rewrite_to_mirror, save_verified_mod,
+        update_cache, CatalogSource,
+    };
+    use crate::api::{
+        api_error::ApiError,
+        catalog_cache::CatalogCache,
+        mod_info::{ModInfo, TEST_CATALOG},
+        mod_version::ModType,
+    };
+    use crate::{sha256_hasher, utils};
+    use reqwest::Client;
+    use std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_util::bytes::Bytes;
+
+    /// Answers every connection with what `respond` returns for the request head, returning
+    /// the base URL of the server.
+    async fn serve<F>(respond: F) -> String
+    where
+        F: Fn(&str) -> Vec<u8> + Send + Sync + 'static,
+    {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let respond = Arc::new(respond);
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let respond = Arc::clone(&respond);
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buffer = [0; 1024];
+                    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                        match stream.read(&mut buffer).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(read) => request.extend_from_slice(&buffer[..read]),
+                        }
+                    }
+
+                    let response = respond(&String::from_utf8_lossy(&request));
+                    let _ = stream.write_all(&response).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        format!("http://{address}")
+    }
+
+    /// A response announcing `length` bytes of body, of which only `body` is sent.
+    fn http_response(status: &str, headers: &[&str], length: usize, body: &[u8]) -> Vec<u8> {
+        let head: String = std::iter::once(format!("HTTP/1.1 {status}"))
+            .chain(["Connection: close".to_string()])
+            .chain(headers.iter().map(ToString::to_string))
+            .chain([format!("Content-Length: {length}"), String::new()])
+            .map(|line| line + "\r\n")
+            .collect();
+
+        let mut response = head.into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    async fn download(base_url: &str) -> reqwest::Response {
+        Client::new()
+            .get(format!("{base_url}/v1/mods/files/113/TestMod.dll"))
+            .send()
+            .await
+            .unwrap()
+    }
+
+    fn hash(data: &'static [u8]) -> String {
+        sha256_hasher::compute_sha256_hash(&Bytes::from_static(data))
+    }
+
+    /// A game folder with an older `Mods/TestMod.dll` already installed.
+    fn game_with_old_mod(name: &str) -> std::path::PathBuf {
+        let game = utils::test_folder(name);
+        std::fs::create_dir_all(game.join("Mods")).unwrap();
+        std::fs::write(game.join("Mods/TestMod.dll"), b"old").unwrap();
+        game
+    }
+
+    fn mods_folder(game: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(game.join("Mods"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn saves_verified_mods() {
+        let game = game_with_old_mod("saves_verified_mods");
+        let base_url = serve(|_| http_response("200 OK", &[], 3, b"new")).await;
+
+        let path = save_verified_mod(
+            download(&base_url).await,
+            113,
+            &hash(b"new"),
+            &ModType::Mod,
+            &game,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(path, game.join("Mods/TestMod.dll"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+        assert_eq!(mods_folder(&game), ["TestMod.dll"]);
+    }
+
+    #[tokio::test]
+    async fn discards_mods_with_another_hash() {
+        let game = game_with_old_mod("discards_mods_with_another_hash");
+        let base_url = serve(|_| http_response("200 OK", &[], 8, b"tampered")).await;
+
+        let result = save_verified_mod(
+            download(&base_url).await,
+            113,
+            &hash(b"new"),
+            &ModType::Mod,
+            &game,
+        )
+        .await;
+
+        assert!(matches!(result, Err(ApiError::InvalidFileHash)));
+        assert_eq!(mods_folder(&game), ["TestMod.dll"]);
+        assert_eq!(
+            std::fs::read(game.join("Mods/TestMod.dll")).unwrap(),
+            b"old"
+        );
+    }
+
+    #[tokio::test]
+    async fn discards_mods_cut_off_midway() {
+        let game = game_with_old_mod("discards_mods_cut_off_midway");
+        // The connection closes after 3 of the 1000 announced bytes
+        let base_url = serve(|_| http_response("200 OK", &[], 1000, b"new")).await;
+
+        let result = save_verified_mod(
+            download(&base_url).await,
+            113,
+            &hash(b"new"),
+            &ModType::Mod,
+            &game,
+        )
+        .await;
+
+        assert!(
+            matches!(result, Err(ApiError::ReqwestError(_))),
+            "{result:?}"
+        );
+        assert_eq!(mods_folder(&game), ["TestMod.dll"]);
+        assert_eq!(
+            std::fs::read(game.join("Mods/TestMod.dll")).unwrap(),
+            b"old"
+        );
+    }
+
+    #[test]
+    fn rewrites_official_links_to_the_mirror() {
+        let mirror = "https://mirror.example.com";
+
+        // (link, rewritten)
+        let cases: [(&str, &str); 7] = [
+            (
+                "https://api.cvrmg.com/v1/mods/download/113",
+                "https://mirror.example.com/v1/mods/download/113",
+            ),
+            (
+                "https://api.cvrmg.com/v1/mods/",
+                "https://mirror.example.com/v1/mods/",
+            ),
+            ("https://api.cvrmg.com/", "https://mirror.example.com/"),
+            ("https://api.cvrmg.com", "https://mirror.example.com"),
+            // Only whole hosts are official, not hosts that start the same
+            (
+                "https://api.cvrmg.com.example.org/v1/mods/download/113",
+                "https://api.cvrmg.com.example.org/v1/mods/download/113",
+            ),
+            (
+                "https://github.com/BTK-Development/BTKUILib/releases/download/2.0.0/BTKUILib.dll",
+                "https://github.com/BTK-Development/BTKUILib/releases/download/2.0.0/BTKUILib.dll",
+            ),
+            ("", ""),
+        ];
+
+        for (link, rewritten) in cases {
+            assert_eq!(rewrite_to_mirror(link, mirror), rewritten, "{link:?}");
+        }
+    }
+
+    #[test]
+    fn orders_base_urls() {
+        let mirrors = [
+            "https://mirror-a.example.com/".to_string(),
+            "  ".to_string(),
+            "https://api.cvrmg.com".to_string(),
+            "https://mirror-b.example.com".to_string(),
+        ];
+
+        assert_eq!(
+            base_urls(None, "https://api.cvrmg.com/".to_string(), &mirrors),
+            [
+                "https://api.cvrmg.com",
+                "https://mirror-a.example.com",
+                "https://mirror-b.example.com"
+            ]
+        );
+        // The mirror that answered last goes first
+        assert_eq!(
+            base_urls(
+                Some("https://mirror-b.example.com".to_string()),
+                "https://api.cvrmg.com".to_string(),
+                &mirrors
+            ),
+            [
+                "https://mirror-b.example.com",
+                "https://api.cvrmg.com",
+                "https://mirror-a.example.com"
+            ]
+        );
+        assert_eq!(
+            base_urls(None, "https://staging.example.com".to_string(), &[]),
+            ["https://staging.example.com"]
+        );
+    }
+
+    /// The API fetch for sources that must never reach it.
+    #[allow(clippy::unused_async)]
+    async fn not_online() -> Result<Vec<ModInfo>, ApiError> {
+        panic!("the API was asked")
+    }
+
+    fn network_error() -> ApiError {
+        ApiError::HttpStatus {
+            url: "https://api.cvrmg.com/v1/mods/".to_string(),
+            status: 503,
+            retry_after: None,
+        }
+    }
+
+    async fn cache_test_catalog(cache_path: &Path) {
+        let mods = serde_json::from_str(TEST_CATALOG).unwrap();
+        CatalogCache::new(mods, None, None)
+            .save(cache_path)
+            .await
+            .unwrap();
+    }
+
+    fn ids(mods: &[ModInfo]) -> Vec<usize> {
+        mods.iter().map(|mod_info| mod_info.id).collect()
+    }
+
+    const TEST_CATALOG_IDS: [usize; 8] = [5, 6, 8, 44, 68, 90, 106, 113];
+
+    #[tokio::test]
+    async fn offline_reads_only_the_cache() {
+        let folder = utils::test_folder("offline_reads_only_the_cache");
+        let cache_path = folder.join("catalog_cache.json");
+
+        let result = load_catalog(&CatalogSource::Offline, &cache_path, not_online()).await;
+        assert!(matches!(result, Err(ApiError::CatalogNotCached)));
+
+        cache_test_catalog(&cache_path).await;
+        let mods = load_catalog(&CatalogSource::Offline, &cache_path, not_online())
+            .await
+            .unwrap();
+        assert_eq!(ids(&mods), TEST_CATALOG_IDS);
+    }
+
+    #[tokio::test]
+    async fn reads_a_catalog_file() {
+        let folder = utils::test_folder("reads_a_catalog_file");
+        let cache_path = folder.join("catalog_cache.json");
+        let catalog = folder.join("mods.json");
+        std::fs::write(&catalog, TEST_CATALOG).unwrap();
+
+        let source = CatalogSource::File(catalog);
+        let mods = load_catalog(&source, &cache_path, not_online())
+            .await
+            .unwrap();
+        assert_eq!(ids(&mods), TEST_CATALOG_IDS);
+        assert!(!cache_path.exists());
+
+        let missing = CatalogSource::File(folder.join("missing.json"));
+        let result = load_catalog(&missing, &cache_path, not_online()).await;
+        assert!(matches!(result, Err(ApiError::IOError(_))));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_cache_after_a_network_error() {
+        let folder = utils::test_folder("falls_back_to_the_cache_after_a_network_error");
+        let cache_path = folder.join("catalog_cache.json");
+        let offline = || async { Err(network_error()) };
+
+        // Nothing cached, the network error is all there is
+        let result = load_catalog(&CatalogSource::Online, &cache_path, offline()).await;
+        assert!(matches!(
+            result,
+            Err(ApiError::HttpStatus { status: 503, .. })
+        ));
+
+        cache_test_catalog(&cache_path).await;
+        let mods = load_catalog(&CatalogSource::Online, &cache_path, offline())
+            .await
+            .unwrap();
+        assert_eq!(ids(&mods), TEST_CATALOG_IDS);
+
+        // Errors other than network ones are not covered up by the cache
+        let result = load_catalog(&CatalogSource::Online, &cache_path, async {
+            Err(ApiError::InvalidFileHash)
+        })
+        .await;
+        assert!(matches!(result, Err(ApiError::InvalidFileHash)));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_network_error_over_a_corrupt_cache() {
+        let folder = utils::test_folder("keeps_the_network_error_over_a_corrupt_cache");
+        let cache_path = folder.join("catalog_cache.json");
+        let offline = || async { Err(network_error()) };
+
+        // Not JSON at all, and JSON whose mods do not parse
+        for contents in ["{ not json", r#"{"fetchedAt": 0, "mods": [{"name": 1}]}"#] {
+            std::fs::write(&cache_path, contents).unwrap();
+            let result = load_catalog(&CatalogSource::Online, &cache_path, offline()).await;
+            assert!(
+                matches!(result, Err(ApiError::HttpStatus { status: 503, .. })),
+                "{contents}: {result:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn revalidates_the_cached_catalog() {
+        const ETAG: &str = "\"catalog-1\"";
+        const LAST_MODIFIED: &str = "Wed, 01 May 2024 10:00:00 GMT";
+        let folder = utils::test_folder("revalidates_the_cached_catalog");
+        let cache_path = folder.join("catalog_cache.json");
+
+        // Answers 304 when the client sends back the ETag it was given
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let base_url = serve({
+            let requests = Arc::clone(&requests);
+            move |request| {
+                let request = request.to_lowercase();
+                requests.lock().unwrap().push(request.clone());
+                if request.contains(&format!("if-none-match: {ETAG}")) {
+                    http_response("304 Not Modified", &[], 0, b"")
+                } else {
+                    let headers = [
+                        format!("ETag: {ETAG}"),
+                        format!("Last-Modified: {LAST_MODIFIED}"),
+                    ];
+                    let headers: Vec<&str> = headers.iter().map(String::as_str).collect();
+                    http_response(
+                        "200 OK",
+                        &headers,
+                        TEST_CATALOG.len(),
+                        TEST_CATALOG.as_bytes(),
+                    )
+                }
+            }
+        })
+        .await;
+        let client = Client::new();
+
+        let fetched = fetch_catalog_if_modified(&client, &base_url, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched.etag.as_deref(), Some(ETAG));
+        assert_eq!(fetched.last_modified.as_deref(), Some(LAST_MODIFIED));
+        let mods = update_cache(None, Some(fetched), &cache_path)
+            .await
+            .unwrap();
+        assert_eq!(ids(&mods), TEST_CATALOG_IDS);
+
+        // The validators come back from the cache and are sent along
+        let mut cached = CatalogCache::load(&cache_path).await.unwrap().unwrap();
+        assert_eq!(cached.etag.as_deref(), Some(ETAG));
+        cached.fetched_at = 0;
+        let refetched = fetch_catalog_if_modified(&client, &base_url, Some(&cached))
+            .await
+            .unwrap();
+        assert!(refetched.is_none());
+        {
+            let requests = requests.lock().unwrap();
+            assert_eq!(requests.len(), 2);
+            assert!(!requests[0].contains("if-none-match"));
+            assert!(requests[1].contains(&format!(
+                "if-modified-since: {}",
+                LAST_MODIFIED.to_lowercase()
+            )));
+        }
+
+        // Not modified keeps the cached catalog and only marks it as current
+        let cached_mods = cached.mods.clone();
+        let mods = update_cache(Some(cached), None, &cache_path).await.unwrap();
+        assert_eq!(ids(&mods), TEST_CATALOG_IDS);
+        let updated = CatalogCache::load(&cache_path).await.unwrap().unwrap();
+        assert!(updated.fetched_at > 0);
+        assert_eq!(updated.etag.as_deref(), Some(ETAG));
+        assert_eq!(updated.last_modified.as_deref(), Some(LAST_MODIFIED));
+        assert_eq!(updated.mods, cached_mods);
     }
 }
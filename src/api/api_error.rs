@@ -1,6 +1,7 @@
 use reqwest::Error as ReqwestError;
 use serde_json::Error as SerdeError;
 use std::io::Error as IoError;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::task::JoinError;
 
@@ -12,6 +13,13 @@ pub(crate) enum ApiError {
     #[error(transparent)]
     IOError(#[from] IoError),
 
+    #[error("HTTP {status} from {url}")]
+    HttpStatus {
+        url: String,
+        status: u16,
+        retry_after: Option<Duration>,
+    },
+
     #[error("Invalid file name")]
     InvalidFileName,
 
@@ -38,4 +46,246 @@ pub(crate) enum ApiError {
 
     #[error("Invalid color hex length")]
     InvalidColorHexLength,
+
+    #[error("Mod not found: {0}")]
+    ModNotFound(String),
+
+    #[error("Mod is not installed: {0}")]
+    ModNotInstalled(String),
+
+    #[error("{mod_name} requires '{requirement}', which is not in the catalog")]
+    UnresolvedRequirement {
+        mod_name: String,
+        requirement: String,
+    },
+
+    #[error("{mod_name} is required by {dependents}")]
+    RequiredByInstalled {
+        mod_name: String,
+        dependents: String,
+    },
+
+    #[error("Interrupted")]
+    Interrupted,
+
+    #[error("{mod_name} was skipped because its requirement {requirement} failed to install")]
+    RequirementFailed {
+        mod_name: String,
+        requirement: String,
+    },
+
+    #[error("{mod_name} is {status}, refused by the install policy {policy}")]
+    RefusedByPolicy {
+        mod_name: String,
+        /// Approval status including the reason the modding group gave, if any.
+        status: String,
+        policy: String,
+    },
+
+    #[error("{mod_name} requires MelonLoader {required}, but {installed} is installed")]
+    IncompatibleLoader {
+        mod_name: String,
+        required: String,
+        installed: String,
+    },
+
+    #[error("Cannot tell the MelonLoader version in {0}")]
+    UnknownLoaderVersion(String),
+
+    #[error("Invalid archive: {0}")]
+    ZipError(#[from] zip::result::ZipError),
+
+    #[error("Not a MelonLoader release archive: {0}")]
+    InvalidLoaderArchive(String),
+
+    #[error("MelonLoader is already installed, {0} exists")]
+    LoaderAlreadyInstalled(String),
+
+    #[error("A previous switch left the old loader in {0}, restore or remove it first")]
+    PreviousLoaderLeftOver(String),
+
+    #[error("Not a .NET assembly: {0}")]
+    InvalidAssembly(String),
+
+    #[error("Dependency cycle: {0}")]
+    DependencyCycle(String),
+
+    #[error("No cached mod catalog, run once without --offline first")]
+    CatalogNotCached,
+
+    #[error(
+        "ChilloutVR folder is not set, pass --chillout-folder or set chilloutFolder in config.json"
+    )]
+    ChilloutFolderNotSet,
+
+    #[error("Invalid config.json: {0}")]
+    InvalidConfig(String),
+}
+
+impl ApiError {
+    /// Process exit code for this error, so scripts can tell failures apart.
+    ///
+    /// * `2` - the requested mod or version does not exist
+    /// * `3` - network or HTTP failure
+    /// * `4` - filesystem failure
+    /// * `5` - malformed data from the API or a local file
+    /// * `6` - downloaded file failed hash verification
+    /// * `7` - missing or invalid configuration
+    /// * `8` - the mod's approval status is not allowed by the install policy, or it
+    ///   targets a `MelonLoader` version that is not installed
+    /// * `1` - anything else
+    pub(crate) fn exit_code(&self) -> u8 {
+        match self {
+            Self::ModNotFound(_)
+            | Self::ModNotInstalled(_)
+            | Self::UnresolvedRequirement { .. }
+            | Self::RequirementFailed { .. }
+            | Self::ModVersionNotFound
+            | Self::NoDownloadUrl => 2,
+            Self::ReqwestError(_) | Self::HttpStatus { .. } => 3,
+            Self::IOError(_) | Self::TokioJoinError(_) => 4,
+            Self::SerdeError(_)
+            | Self::InvalidFileName
+            | Self::InvalidColoreLength
+            | Self::ParseIntError(_)
+            | Self::InvalidColorHexLength
+            | Self::InvalidAssembly(_)
+            | Self::ZipError(_)
+            | Self::InvalidLoaderArchive(_)
+            | Self::DependencyCycle(_) => 5,
+            Self::InvalidFileHash => 6,
+            Self::LoaderAlreadyInstalled(_)
+            | Self::PreviousLoaderLeftOver(_)
+            | Self::RequiredByInstalled { .. } => 1,
+            // Shells report a process stopped by SIGINT as 128 + 2
+            Self::Interrupted => 130,
+            Self::ChilloutFolderNotSet | Self::CatalogNotCached | Self::InvalidConfig(_) => 7,
+            Self::RefusedByPolicy { .. }
+            | Self::IncompatibleLoader { .. }
+            | Self::UnknownLoaderVersion(_) => 8,
+        }
+    }
+
+    /// Whether the failure is transient, a dropped connection or an overloaded server,
+    /// and the request is worth repeating.
+    pub(crate) fn is_retryable(&self) -> bool {
+        match self {
+            Self::ReqwestError(err) => {
+                err.is_timeout() || err.is_connect() || err.is_request() || err.is_body()
+            }
+            Self::HttpStatus { status, .. } => matches!(status, 408 | 429 | 500 | 502 | 503 | 504),
+            _ => false,
+        }
+    }
+
+    /// Whether the server could not be reached at all.
+    pub(crate) fn is_connect(&self) -> bool {
+        matches!(self, Self::ReqwestError(err) if err.is_connect())
+    }
+
+    /// Delay the server asked for with `Retry-After`, only honored on 429 and 503.
+    pub(crate) fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::HttpStatus {
+                status: 429 | 503,
+                retry_after,
+                ..
+            } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiError;
+    use std::time::Duration;
+
+    #[test]
+    fn retries_transient_statuses() {
+        let asked = Some(Duration::from_secs(5));
+
+        // (status, retryable, honors Retry-After)
+        let cases: [(u16, bool, bool); 13] = [
+            (408, true, false),
+            (429, true, true),
+            (500, true, false),
+            (502, true, false),
+            (503, true, true),
+            (504, true, false),
+            (400, false, false),
+            (401, false, false),
+            (403, false, false),
+            (404, false, false),
+            (410, false, false),
+            (413, false, false),
+            (422, false, false),
+        ];
+
+        for (status, retryable, honored) in cases {
+            let err = ApiError::HttpStatus {
+                url: "https://api.cvrmg.com/v1/mods/".to_string(),
+                status,
+                retry_after: asked,
+            };
+            assert_eq!(err.is_retryable(), retryable, "{status}");
+            assert_eq!(err.retry_after(), asked.filter(|_| honored), "{status}");
+        }
+
+        assert!(!ApiError::InvalidFileHash.is_retryable());
+        assert!(!ApiError::ModNotFound("BTKUILib".to_string()).is_retryable());
+        assert_eq!(ApiError::InvalidFileHash.retry_after(), None);
+    }
+
+    #[test]
+    fn exit_codes() {
+        let text = || "BTKUILib".to_string();
+        // (error, exit code)
+        let cases: Vec<(ApiError, u8)> = vec![
+            (ApiError::ModNotFound(text()), 2),
+            (ApiError::ModNotInstalled(text()), 2),
+            (ApiError::ModVersionNotFound, 2),
+            (ApiError::NoDownloadUrl, 2),
+            (
+                ApiError::HttpStatus {
+                    url: text(),
+                    status: 503,
+                    retry_after: None,
+                },
+                3,
+            ),
+            (std::io::Error::other("disk full").into(), 4),
+            (serde_json::from_str::<u8>("x").unwrap_err().into(), 5),
+            ("x".parse::<u8>().unwrap_err().into(), 5),
+            (zip::result::ZipError::FileNotFound.into(), 5),
+            (ApiError::InvalidLoaderArchive(text()), 5),
+            (ApiError::DependencyCycle(text()), 5),
+            (ApiError::InvalidFileHash, 6),
+            (ApiError::ChilloutFolderNotSet, 7),
+            (ApiError::CatalogNotCached, 7),
+            (ApiError::InvalidConfig(text()), 7),
+            (
+                ApiError::RefusedByPolicy {
+                    mod_name: text(),
+                    status: text(),
+                    policy: text(),
+                },
+                8,
+            ),
+            (ApiError::UnknownLoaderVersion(text()), 8),
+            (ApiError::LoaderAlreadyInstalled(text()), 1),
+            (
+                ApiError::RequiredByInstalled {
+                    mod_name: text(),
+                    dependents: text(),
+                },
+                1,
+            ),
+            (ApiError::Interrupted, 130),
+        ];
+
+        for (err, code) in cases {
+            assert_eq!(err.exit_code(), code, "{err:?}");
+        }
+    }
 }
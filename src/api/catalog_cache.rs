@@ -0,0 +1,82 @@
+use super::{api_error::ApiError, mod_info::ModInfo};
+use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const CATALOG_CACHE_FILE_NAME: &str = "catalog_cache.json";
+
+/// The last mod list fetched from the API, kept next to `config.json` for offline use.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CatalogCache {
+    /// Seconds since the Unix epoch.
+    pub(crate) fetched_at: u64,
+    /// Validators from the response, sent back on the next fetch to allow a `304 Not Modified`.
+    #[serde(default)]
+    pub(crate) etag: Option<String>,
+    #[serde(default)]
+    pub(crate) last_modified: Option<String>,
+    /// The API response as it was received, so new fields survive a round trip.
+    pub(crate) mods: serde_json::Value,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Where the catalog is cached, next to `config.json` in the current directory.
+pub(crate) fn cache_path() -> Result<PathBuf, ApiError> {
+    Ok(std::env::current_dir()?.join(CATALOG_CACHE_FILE_NAME))
+}
+
+impl CatalogCache {
+    pub(crate) fn new(
+        mods: serde_json::Value,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> Self {
+        Self {
+            fetched_at: unix_now(),
+            etag,
+            last_modified,
+            mods,
+        }
+    }
+
+    /// Marks the cached catalog as confirmed current by the API.
+    pub(crate) fn touch(&mut self) {
+        self.fetched_at = unix_now();
+    }
+
+    /// Reads the cache at `path`, `None` if nothing was cached yet.
+    pub(crate) async fn load(path: &Path) -> Result<Option<Self>, ApiError> {
+        if !path.try_exists()? {
+            return Ok(None);
+        }
+
+        let contents = tokio::fs::read_to_string(path).await?;
+        Ok(Some(serde_json::from_str(&contents)?))
+    }
+
+    pub(crate) async fn save(&self, path: &Path) -> Result<(), ApiError> {
+        let contents = serde_json::to_vec(self)?;
+        utils::write_file_atomic(path, &contents).await
+    }
+
+    pub(crate) fn parse_mods(&self) -> Result<Vec<ModInfo>, ApiError> {
+        Ok(Vec::<ModInfo>::deserialize(&self.mods)?)
+    }
+
+    /// How long ago the catalog was fetched.
+    pub(crate) fn age(&self) -> Duration {
+        let fetched_at = UNIX_EPOCH + Duration::from_secs(self.fetched_at);
+        SystemTime::now()
+            .duration_since(fetched_at)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,257 @@
+use regex::Regex;
+use std::{cmp::Ordering, fmt, sync::LazyLock};
+
+// "2024r176", "2022r171p4", "2024r176 ex1"
+static BUILD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?P<year>\d{4})r(?P<release>\d+)(?:p(?P<patch>\d+))?(?:\s*ex(?P<experimental>\d+))?$")
+        .expect("Invalid game build regex")
+});
+
+/// Values authors use for mods that work with every game build, misspellings included.
+const UNIVERSAL: [&str; 3] = ["universal", "univsersal", "all"];
+
+/// A ChilloutVR build such as `2024r176`, `2022r171p4` or `2024r176 ex1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct GameBuild {
+    pub(crate) year: u16,
+    pub(crate) release: u16,
+    pub(crate) patch: u16,
+    /// Experimental branch build, these come out before the release they are named after.
+    pub(crate) experimental: Option<u16>,
+}
+
+impl Ord for GameBuild {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.year, self.release, self.patch)
+            .cmp(&(other.year, other.release, other.patch))
+            .then_with(|| match (self.experimental, other.experimental) {
+                (None, None) => Ordering::Equal,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(this), Some(other)) => this.cmp(&other),
+            })
+    }
+}
+
+impl PartialOrd for GameBuild {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for GameBuild {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}r{}", self.year, self.release)?;
+        if self.patch > 0 {
+            write!(f, "p{}", self.patch)?;
+        }
+        if let Some(experimental) = self.experimental {
+            write!(f, " ex{experimental}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A build for tests, `experimental` is the `ex` number of experimental branch builds.
+#[cfg(test)]
+pub(crate) fn test_build(
+    year: u16,
+    release: u16,
+    patch: u16,
+    experimental: Option<u16>,
+) -> GameBuild {
+    GameBuild {
+        year,
+        release,
+        patch,
+        experimental,
+    }
+}
+
+/// `ModVersion::game_version`, normalized.
+///
+/// Ordered as unknown values first, then builds from oldest to newest, then `Universal`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum GameVersion {
+    /// Anything that is not a build or a wildcard, like `None`, `-` or a Steam build id.
+    Unknown(String),
+    Build(GameBuild),
+    /// Works with every build.
+    Universal,
+}
+
+impl GameVersion {
+    /// Parses a raw game version, never fails since unrecognized values become `Unknown`.
+    pub(crate) fn parse(raw: &str) -> Self {
+        let text = raw.trim();
+
+        if UNIVERSAL
+            .iter()
+            .any(|universal| text.eq_ignore_ascii_case(universal))
+        {
+            return Self::Universal;
+        }
+
+        let Some(captures) = BUILD.captures(text) else {
+            return Self::Unknown(text.to_string());
+        };
+        let number = |name: &str| captures.name(name).map(|m| m.as_str().parse::<u16>());
+
+        match (
+            number("year"),
+            number("release"),
+            number("patch").transpose(),
+            number("experimental").transpose(),
+        ) {
+            (Some(Ok(year)), Some(Ok(release)), Ok(patch), Ok(experimental)) => {
+                Self::Build(GameBuild {
+                    year,
+                    release,
+                    patch: patch.unwrap_or(0),
+                    experimental,
+                })
+            }
+            // Numbers too large for a build are not one
+            _ => Self::Unknown(text.to_string()),
+        }
+    }
+
+    /// Whether a mod made for this game version works with the installed `build`.
+    ///
+    /// Patches and experimental builds keep the release's mod compatibility, so only the year
+    /// and release have to be equal. `Universal` matches every build, unknown values none.
+    pub(crate) fn matches(&self, build: &GameBuild) -> bool {
+        match self {
+            Self::Unknown(_) => false,
+            Self::Build(this) => (this.year, this.release) == (build.year, build.release),
+            Self::Universal => true,
+        }
+    }
+
+    /// The build, `None` for `Universal` and unknown values.
+    pub(crate) fn build(&self) -> Option<&GameBuild> {
+        match self {
+            Self::Build(build) => Some(build),
+            _ => None,
+        }
+    }
+}
+
+impl Ord for GameVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Unknown(this), Self::Unknown(other)) => this.cmp(other),
+            (Self::Build(this), Self::Build(other)) => this.cmp(other),
+            (Self::Universal, Self::Universal) => Ordering::Equal,
+            (Self::Unknown(_), _) | (Self::Build(_), Self::Universal) => Ordering::Less,
+            (Self::Universal, _) | (Self::Build(_), Self::Unknown(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for GameVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for GameVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(raw) => write!(f, "{raw}"),
+            Self::Build(build) => write!(f, "{build}"),
+            Self::Universal => write!(f, "Universal"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{test_build, GameVersion};
+
+    #[test]
+    fn parses_catalog_game_versions() {
+        let cases = [
+            (
+                "2024r176",
+                GameVersion::Build(test_build(2024, 176, 0, None)),
+            ),
+            (
+                "2022r171p4",
+                GameVersion::Build(test_build(2022, 171, 4, None)),
+            ),
+            (
+                "2024r176 ex1",
+                GameVersion::Build(test_build(2024, 176, 0, Some(1))),
+            ),
+            (
+                "2024r176ex2",
+                GameVersion::Build(test_build(2024, 176, 0, Some(2))),
+            ),
+            (
+                " 2023R173 ",
+                GameVersion::Build(test_build(2023, 173, 0, None)),
+            ),
+            ("Universal", GameVersion::Universal),
+            ("Univsersal", GameVersion::Universal),
+            ("all", GameVersion::Universal),
+            ("None", GameVersion::Unknown("None".to_string())),
+            ("-", GameVersion::Unknown("-".to_string())),
+            (" 10165732 ", GameVersion::Unknown("10165732".to_string())),
+            ("", GameVersion::Unknown(String::new())),
+            ("2024r99999", GameVersion::Unknown("2024r99999".to_string())),
+        ];
+
+        for (raw, expected) in cases {
+            assert_eq!(GameVersion::parse(raw), expected, "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn displays_normalized() {
+        for (raw, expected) in [
+            ("2024r176 ex1", "2024r176 ex1"),
+            ("2022r171p4", "2022r171p4"),
+            ("2024r176p0", "2024r176"),
+            ("univsersal", "Universal"),
+            (" - ", "-"),
+        ] {
+            assert_eq!(GameVersion::parse(raw).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn orders_builds() {
+        let ordered = [
+            "-",
+            "None",
+            "2022r171",
+            "2022r171p4",
+            "2024r176 ex1",
+            "2024r176 ex2",
+            "2024r176",
+            "2024r177",
+            "Universal",
+        ]
+        .map(GameVersion::parse);
+
+        for pair in ordered.windows(2) {
+            assert!(pair[0] < pair[1], "{} < {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn matches_installed_build() {
+        let installed = test_build(2024, 176, 2, None);
+
+        assert!(GameVersion::parse("2024r176").matches(&installed));
+        assert!(GameVersion::parse("2024r176p1").matches(&installed));
+        assert!(GameVersion::parse("2024r176 ex3").matches(&installed));
+        assert!(GameVersion::parse("Universal").matches(&installed));
+        assert!(GameVersion::parse("All").matches(&installed));
+        assert!(!GameVersion::parse("2024r175").matches(&installed));
+        assert!(!GameVersion::parse("2023r176").matches(&installed));
+        assert!(!GameVersion::parse("None").matches(&installed));
+        assert!(!GameVersion::parse("10165732").matches(&installed));
+    }
+}
@@ -0,0 +1,226 @@
+use regex::Regex;
+use semver::Version;
+use std::{fmt, sync::LazyLock};
+
+// "v0.5.4 Open-Beta", "0.5.4+", "0.5.4.0", ">=0.6.0", "v0.6.2"
+static LOADER_VERSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?P<minimum>>=\s*)?v?(?P<major>\d+)(?:\.(?P<minor>\d+))?(?:\.(?P<patch>\d+))?(?:\.\d+)?(?P<plus>\+)?")
+        .expect("Invalid loader version regex")
+});
+
+/// Parses a `MelonLoader` version such as `v0.5.4 Open-Beta` or `0.5.4.0`.
+///
+/// The fourth part and release channel suffixes are dropped, the loader only ever bumps
+/// the first three parts.
+pub(crate) fn parse_loader_version(raw: &str) -> Option<Version> {
+    parse(raw).map(|(version, _)| version)
+}
+
+fn parse(raw: &str) -> Option<(Version, Bound)> {
+    let captures = LOADER_VERSION.captures(raw.trim())?;
+    let number = |name: &str| {
+        captures
+            .name(name)
+            .map_or(Some(0), |m| m.as_str().parse::<u64>().ok())
+    };
+
+    let version = Version::new(number("major")?, number("minor")?, number("patch")?);
+    let bound = if captures.name("minimum").is_some() || captures.name("plus").is_some() {
+        Bound::Minimum
+    } else {
+        Bound::Compatible
+    };
+
+    Some((version, bound))
+}
+
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash)]
+enum Bound {
+    /// `0.5.4+`, this version or anything newer.
+    Minimum,
+    /// `0.5.4`, newer versions up to the next breaking release, like a caret requirement.
+    Compatible,
+    /// This version only, what `VerifyLoaderVersion` without `IsMinimum` asks for.
+    Exact,
+}
+
+/// `ModVersion::loader_version`, parsed.
+#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub(crate) struct LoaderRequirement {
+    version: Option<(Version, Bound)>,
+    raw: String,
+}
+
+impl LoaderRequirement {
+    /// Parses a raw loader version, anything without a version number requires nothing.
+    pub(crate) fn parse(raw: &str) -> Self {
+        Self {
+            version: parse(raw),
+            raw: raw.trim().to_string(),
+        }
+    }
+
+    /// The requirement of a `VerifyLoaderVersion` attribute, `version` or anything newer when
+    /// `is_minimum` is set and exactly `version` otherwise.
+    pub(crate) fn from_attribute(version: Version, is_minimum: bool) -> Self {
+        let bound = if is_minimum {
+            Bound::Minimum
+        } else {
+            Bound::Exact
+        };
+
+        Self {
+            raw: version.to_string(),
+            version: Some((version, bound)),
+        }
+    }
+
+    /// The lowest loader version the mod works with, `None` if it did not name one.
+    pub(crate) fn minimum(&self) -> Option<&Version> {
+        self.version.as_ref().map(|(version, _)| version)
+    }
+
+    /// Whether the mod works with the loader version `installed`.
+    ///
+    /// Compatible versions share the major version, or the minor version while the major
+    /// version is `0`, since `MelonLoader` breaks mods between `0.5` and `0.6`.
+    pub(crate) fn matches(&self, installed: &Version) -> bool {
+        let Some((required, bound)) = &self.version else {
+            return true;
+        };
+        let installed = Version::new(installed.major, installed.minor, installed.patch);
+
+        if installed < *required {
+            return false;
+        }
+
+        match bound {
+            Bound::Minimum => true,
+            Bound::Exact => installed == *required,
+            Bound::Compatible if required.major == 0 => {
+                installed.major == 0 && installed.minor == required.minor
+            }
+            Bound::Compatible => installed.major == required.major,
+        }
+    }
+}
+
+impl fmt::Display for LoaderRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some((version, Bound::Minimum)) => write!(f, ">={version}"),
+            Some((version, Bound::Compatible)) => write!(f, "^{version}"),
+            Some((version, Bound::Exact)) => write!(f, "={version}"),
+            None if self.raw.is_empty() => write!(f, "any"),
+            None => write!(f, "{} (any)", self.raw),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_loader_version, LoaderRequirement};
+    use semver::Version;
+
+    #[test]
+    fn matches_installed_loaders() {
+        // (raw, displayed, installed versions that match, ones that do not)
+        let cases: [(&str, &str, &[&str], &[&str]); 8] = [
+            (
+                "0.6.1",
+                "^0.6.1",
+                &["0.6.1", "0.6.2", "0.6.1-alpha"],
+                &["0.6.0", "0.7.0", "1.0.0"],
+            ),
+            (
+                ">=0.6",
+                ">=0.6.0",
+                &["0.6.0", "0.6.5", "0.7.0", "1.2.0"],
+                &["0.5.7"],
+            ),
+            ("0.5.x", "^0.5.0", &["0.5.0", "0.5.7"], &["0.4.3", "0.6.0"]),
+            (
+                "v0.5.4 Open-Beta",
+                "^0.5.4",
+                &["0.5.4", "0.5.7"],
+                &["0.5.3", "0.6.0"],
+            ),
+            ("0.5.4+", ">=0.5.4", &["0.5.4", "0.6.1"], &["0.5.3"]),
+            (
+                "1.2.0.0",
+                "^1.2.0",
+                &["1.2.0", "1.9.0"],
+                &["1.1.9", "2.0.0"],
+            ),
+            ("", "any", &["0.5.4", "0.6.1"], &[]),
+            (
+                "latest please",
+                "latest please (any)",
+                &["0.5.4", "0.6.1"],
+                &[],
+            ),
+        ];
+
+        for (raw, displayed, matching, not_matching) in cases {
+            let requirement = LoaderRequirement::parse(raw);
+            assert_eq!(requirement.to_string(), displayed, "{raw:?}");
+
+            for installed in matching {
+                let installed = Version::parse(installed).unwrap();
+                assert!(requirement.matches(&installed), "{raw:?} with {installed}");
+            }
+            for installed in not_matching {
+                let installed = Version::parse(installed).unwrap();
+                assert!(!requirement.matches(&installed), "{raw:?} with {installed}");
+            }
+        }
+    }
+
+    #[test]
+    fn parses_loader_versions() {
+        let cases = [
+            ("v0.6.1 Open-Beta", Some(Version::new(0, 6, 1))),
+            ("0.5.4.0", Some(Version::new(0, 5, 4))),
+            (" V0.6 ", Some(Version::new(0, 6, 0))),
+            (">= 0.6.0", Some(Version::new(0, 6, 0))),
+            ("", None),
+            ("None", None),
+            ("99999999999999999999.0", None),
+        ];
+
+        for (raw, expected) in cases {
+            assert_eq!(parse_loader_version(raw), expected, "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn minimum_is_the_named_version() {
+        assert_eq!(
+            LoaderRequirement::parse(">=0.6").minimum(),
+            Some(&Version::new(0, 6, 0))
+        );
+        assert_eq!(LoaderRequirement::parse("garbage").minimum(), None);
+    }
+
+    #[test]
+    fn attribute_requirements_are_minimum_or_exact() {
+        let minimum = LoaderRequirement::from_attribute(Version::new(0, 6, 1), true);
+        let exact = LoaderRequirement::from_attribute(Version::new(0, 6, 1), false);
+
+        assert_eq!(minimum.to_string(), ">=0.6.1");
+        assert_eq!(exact.to_string(), "=0.6.1");
+        assert_eq!(exact.minimum(), Some(&Version::new(0, 6, 1)));
+
+        for (installed, minimum_matches, exact_matches) in [
+            ("0.6.0", false, false),
+            ("0.6.1", true, true),
+            ("0.6.1-alpha", true, true),
+            ("0.6.2", true, false),
+            ("1.0.0", true, false),
+        ] {
+            let installed = Version::parse(installed).unwrap();
+            assert_eq!(minimum.matches(&installed), minimum_matches, "{installed}");
+            assert_eq!(exact.matches(&installed), exact_matches, "{installed}");
+        }
+    }
+}
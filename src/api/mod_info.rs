@@ -13,8 +13,90 @@ pub(crate) struct ModInfo {
     pub(crate) versions: Vec<ModVersion>,
 }
 
+impl ModInfo {
+    /// The newest version published for this mod, the API lists it first.
+    pub(crate) fn latest_version(&self) -> Option<&ModVersion> {
+        self.versions.first()
+    }
+
+    /// Whether `name` is this mod's name or one of its aliases, ignoring case.
+    pub(crate) fn matches_name(&self, name: &str) -> bool {
+        let name = name.trim();
+        self.name.eq_ignore_ascii_case(name)
+            || self
+                .aliases
+                .iter()
+                .flatten()
+                .any(|alias| alias.trim().eq_ignore_ascii_case(name))
+    }
+
+    /// Case-insensitive substring search over name, aliases, category and description.
+    pub(crate) fn matches_search(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        let contains = |s: &str| s.to_lowercase().contains(&query);
+
+        contains(&self.name)
+            || self.aliases.iter().flatten().any(|alias| contains(alias))
+            || self.category.as_deref().is_some_and(contains)
+            || self
+                .latest_version()
+                .is_some_and(|version| contains(&version.description))
+    }
+}
+
+/// Finds a mod by its numeric id, name or alias.
+pub(crate) fn find_mod<'a>(mods: &'a [ModInfo], query: &str) -> Option<&'a ModInfo> {
+    if let Ok(id) = query.trim().parse::<usize>() {
+        if let Some(mod_info) = mods.iter().find(|mod_info| mod_info.id == id) {
+            return Some(mod_info);
+        }
+    }
+
+    mods.iter().find(|mod_info| mod_info.matches_name(query))
+}
+
 pub(crate) fn into_hashmap(mods: Vec<ModInfo>) -> HashMap<String, ModInfo> {
     mods.into_iter()
         .map(|mod_info| (mod_info.name.clone(), mod_info))
         .collect()
-}
\ No newline at end of file
+}
+
+/// An excerpt of the API's catalog response, for tests.
+#[cfg(test)]
+pub(crate) const TEST_CATALOG: &str = include_str!("test_catalog.json");
+
+/// A catalog entry with a single version, for tests.
+///
+/// `requirements` are parsed like the API's raw requirement strings.
+#[cfg(test)]
+pub(crate) fn test_mod(id: usize, name: &str, version: &str, requirements: &[&str]) -> ModInfo {
+    use crate::api::game_version::GameVersion;
+    use crate::api::loader_version::LoaderRequirement;
+    use crate::api::mod_version::{ApprovalStatus, ModType};
+    use crate::api::requirement::Requirement;
+
+    ModInfo {
+        id,
+        name: name.to_string(),
+        aliases: None,
+        category: None,
+        versions: vec![ModVersion {
+            approval_status: ApprovalStatus::Approved,
+            name: name.to_string(),
+            mod_version: semver::Version::parse(version).expect("Invalid test version"),
+            game_version: GameVersion::parse(""),
+            loader_version: LoaderRequirement::parse(""),
+            mod_type: ModType::Mod,
+            authors: Vec::new(),
+            description: String::new(),
+            download_link: format!("https://api.cvrmg.com/v1/mods/download/{id}"),
+            source_link: String::new(),
+            embed_color: String::new(),
+            hash: String::new(),
+            requirements: requirements
+                .iter()
+                .filter_map(|raw| Requirement::parse(raw))
+                .collect(),
+        }],
+    }
+}
@@ -1,11 +1,15 @@
 use semver::Version;
 use serde::de::{self};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
 use std::str::FromStr;
 
+use super::game_version::GameVersion;
+use super::loader_version::LoaderRequirement;
+use super::requirement::Requirement;
 use super::ApiError;
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Default)]
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Default, Deserialize, Serialize)]
 pub(crate) enum ApprovalStatus {
     #[default]
     AwaitingApproval,
@@ -15,40 +19,76 @@ pub(crate) enum ApprovalStatus {
     Broken(Option<String>),
 }
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Deserialize)]
+impl ApprovalStatus {
+    /// Whether the modding group flagged the mod as not working with the current game.
+    pub(crate) fn is_flagged(&self) -> bool {
+        matches!(self, Self::Broken(_) | Self::Outdated(_))
+    }
+}
+
+impl fmt::Display for ApprovalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AwaitingApproval => write!(f, "Awaiting approval"),
+            Self::Approved => write!(f, "Approved"),
+            Self::Outdated(None) => write!(f, "Outdated"),
+            Self::Outdated(Some(reason)) => write!(f, "Outdated ({reason})"),
+            Self::Broken(None) => write!(f, "Broken"),
+            Self::Broken(Some(reason)) => write!(f, "Broken ({reason})"),
+        }
+    }
+}
+
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Deserialize, Serialize, Default)]
 pub(crate) enum ModType {
+    #[default]
     Mod,
     Plugin,
 }
 
-impl Default for ModType {
-    fn default() -> Self {
-        Self::Mod
+impl ModType {
+    /// Name of the folder inside the ChilloutVR directory this type is loaded from.
+    pub(crate) fn folder_name(&self) -> &'static str {
+        match self {
+            Self::Mod => "Mods",
+            Self::Plugin => "Plugins",
+        }
+    }
+}
+
+impl fmt::Display for ModType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mod => write!(f, "Mod"),
+            Self::Plugin => write!(f, "Plugin"),
+        }
     }
 }
 
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ModVersion {
-    #[serde(deserialize_with = "deserialize_approval_status", default)]
+    #[serde(flatten, deserialize_with = "deserialize_approval_status")]
     pub approval_status: ApprovalStatus,
     pub name: String,
     #[serde(deserialize_with = "parse_semver")]
     #[allow(clippy::struct_field_names)]
     pub mod_version: Version,
-    pub game_version: String,
-    pub loader_version: String,
+    #[serde(deserialize_with = "parse_game_version")]
+    pub game_version: GameVersion,
+    #[serde(deserialize_with = "parse_loader_requirement")]
+    pub loader_version: LoaderRequirement,
     #[serde(deserialize_with = "deserialize_mod_type")]
     pub mod_type: ModType,
-    #[serde(deserialize_with = "parse_authors")]
+    #[serde(alias = "author", deserialize_with = "parse_authors")]
     pub authors: Vec<String>,
     pub description: String,
     pub download_link: String,
     pub source_link: String,
     pub embed_color: String,
     pub hash: String,
-    #[serde(default)]
-    pub requirements: Option<Vec<Option<String>>>,
+    #[serde(default, deserialize_with = "parse_requirements")]
+    pub requirements: Vec<Requirement>,
 }
 
 impl ModVersion {
@@ -98,36 +138,16 @@ impl ModVersion {
     }
 
     pub(crate) fn get_requirements(&self) -> Option<Vec<String>> {
-        self.requirements.as_ref().and_then(|requirements| {
-            let reqs: Vec<String> = requirements
-                .iter()
-                .filter_map(|req| req.as_ref())
-                .filter_map(|req| {
-                    // Some use [name](url) so
-                    extract_modname(req)
-                })
-                .collect();
-
-            if reqs.is_empty() {
-                None
-            } else {
-                Some(reqs)
-            }
-        })
-    }
-}
-
-fn extract_modname(req: &str) -> Option<String> {
-    if req.contains('[') && req.contains(']') {
-        // Handle the [modname] case
-        req.trim_start_matches('[')
-            .split(']')
-            .next()
-            .map(|name| name.trim().to_string())
-            .filter(|name| !name.is_empty())
-    } else {
-        // Handle the plain modname case
-        Some(req.trim().to_string())
+        if self.requirements.is_empty() {
+            None
+        } else {
+            Some(
+                self.requirements
+                    .iter()
+                    .map(|requirement| requirement.name.clone())
+                    .collect(),
+            )
+        }
     }
 }
 
@@ -156,13 +176,44 @@ where
 {
     let authors: Vec<String> = String::deserialize(deserializer)?
         .split([',', '&'])
-        .filter(|s| !s.trim().is_empty())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
         .map(std::string::ToString::to_string)
         .collect();
 
     Ok(authors)
 }
 
+fn parse_requirements<'de, D>(deserializer: D) -> Result<Vec<Requirement>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let requirements: Option<Vec<Option<String>>> = Option::deserialize(deserializer)?;
+
+    Ok(requirements
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|requirement| Requirement::parse(&requirement))
+        .collect())
+}
+
+fn parse_game_version<'de, D>(deserializer: D) -> Result<GameVersion, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = Option::<String>::deserialize(deserializer)?;
+    Ok(GameVersion::parse(raw.as_deref().unwrap_or_default()))
+}
+
+fn parse_loader_requirement<'de, D>(deserializer: D) -> Result<LoaderRequirement, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = Option::<String>::deserialize(deserializer)?;
+    Ok(LoaderRequirement::parse(raw.as_deref().unwrap_or_default()))
+}
+
 fn deserialize_approval_status<'de, D>(deserializer: D) -> Result<ApprovalStatus, D::Error>
 where
     D: Deserializer<'de>,
@@ -175,12 +226,13 @@ where
     }
 
     let helper = ApprovalStatusHelper::deserialize(deserializer)?;
+    let reason = helper.reason.filter(|reason| !reason.trim().is_empty());
 
     match helper.approval_status {
         0 => Ok(ApprovalStatus::AwaitingApproval),
         1 => Ok(ApprovalStatus::Approved),
-        2 => Ok(ApprovalStatus::Broken(helper.reason)),
-        3 => Ok(ApprovalStatus::Outdated(helper.reason)),
+        2 => Ok(ApprovalStatus::Broken(reason)),
+        3 => Ok(ApprovalStatus::Outdated(reason)),
 
         status => Err(de::Error::custom(format!(
             "Invalid approvalStatus value: {status}"
@@ -193,13 +245,13 @@ fn parse_semver<'de, D>(deserializer: D) -> Result<semver::Version, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let version_str: &str = Deserialize::deserialize(deserializer)?;
-    let normalized_version = normalize_version(version_str);
+    let version_str = String::deserialize(deserializer)?;
+    let normalized_version = normalize_version(&version_str);
     semver::Version::from_str(&normalized_version).map_err(de::Error::custom)
 }
 
 // Function to normalize version strings
-fn normalize_version(version: &str) -> String {
+pub(crate) fn normalize_version(version: &str) -> String {
     let mut parts = version.split('-'); // Split on pre-release part
     let version_part = parts.next().unwrap();
 
@@ -220,3 +272,117 @@ fn normalize_version(version: &str) -> String {
         normalized_version
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ApprovalStatus, ModType, ModVersion};
+    use crate::api::mod_info::{ModInfo, TEST_CATALOG};
+    use semver::Version;
+
+    fn catalog() -> Vec<ModInfo> {
+        serde_json::from_str(TEST_CATALOG).unwrap()
+    }
+
+    fn latest(mods: &[ModInfo], id: usize) -> &ModVersion {
+        let mod_info = mods.iter().find(|mod_info| mod_info.id == id).unwrap();
+        mod_info.latest_version().unwrap()
+    }
+
+    #[test]
+    fn reads_the_flattened_approval_status() {
+        let mods = catalog();
+        // (mod id, status)
+        let cases = [
+            (5, ApprovalStatus::Approved),
+            // A reason on an approved mod is dropped
+            (113, ApprovalStatus::Approved),
+            (
+                8,
+                ApprovalStatus::Broken(Some("No longer works".to_string())),
+            ),
+            (
+                44,
+                ApprovalStatus::Outdated(Some(
+                    "breaks constraints, creator wish to retire it when broken".to_string(),
+                )),
+            ),
+        ];
+
+        for (id, status) in cases {
+            assert_eq!(latest(&mods, id).approval_status, status, "mod {id}");
+        }
+    }
+
+    /// Name, whether it is optional and the linked catalog id of a requirement.
+    type RequirementParts<'a> = (&'a str, bool, Option<usize>);
+
+    #[test]
+    fn reads_requirements() {
+        let mods = catalog();
+        // (mod id, [(name, optional, linked mod id)])
+        let cases: [(usize, &[RequirementParts]); 5] = [
+            (5, &[("BTKUILib", false, Some(113))]),
+            (68, &[("BTKUILib", true, Some(113))]),
+            (
+                106,
+                &[
+                    ("BTKUILib", false, Some(113)),
+                    ("UIExpansionKit", false, Some(90)),
+                ],
+            ),
+            // Placeholders and `null` are no requirements
+            (8, &[]),
+            (113, &[]),
+        ];
+
+        for (id, expected) in cases {
+            let requirements: Vec<RequirementParts> = latest(&mods, id)
+                .requirements
+                .iter()
+                .map(|requirement| {
+                    (
+                        requirement.name.as_str(),
+                        requirement.optional,
+                        requirement.mod_id,
+                    )
+                })
+                .collect();
+            assert_eq!(requirements, expected, "mod {id}");
+        }
+    }
+
+    #[test]
+    fn reads_versions_types_and_authors() {
+        let mods = catalog();
+        // (mod id, version, type, authors)
+        let cases: [(usize, &str, ModType, &[&str]); 3] = [
+            (6, "1.0.4", ModType::Plugin, &["Herp Derpinstine"]),
+            (90, "1.1.5", ModType::Mod, &["knah", "DDAkebono"]),
+            (106, "2.1.4", ModType::Mod, &["Nirvash", "NotAKidoS"]),
+        ];
+
+        for (id, version, mod_type, authors) in cases {
+            let latest = latest(&mods, id);
+            assert_eq!(
+                latest.mod_version,
+                Version::parse(version).unwrap(),
+                "mod {id}"
+            );
+            assert_eq!(latest.mod_type, mod_type, "mod {id}");
+            assert_eq!(latest.authors, authors, "mod {id}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_approval_statuses() {
+        let mut entry: serde_json::Value = serde_json::from_str(TEST_CATALOG).unwrap();
+        let version = &mut entry[0]["versions"][0];
+        version["approvalStatus"] = 7.into();
+
+        let err = serde_json::from_value::<ModVersion>(version.take()).unwrap_err();
+        assert!(
+            err.to_string().contains("Invalid approvalStatus value: 7"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use regex::Regex;
+use std::{fmt, sync::LazyLock};
+
+static MARKDOWN_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\[(?P<name>[^\]]*)\]\((?P<url>[^)]*)\)").expect("Invalid markdown link regex")
+});
+
+static MOD_ID_IN_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"/mods/(?:download|files)/(\d+)").expect("Invalid mod id link regex")
+});
+
+// "Optional but recommended - X", "Requires - X", "Supports - X"
+static NAME_PREFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?P<kind>optional(?: but recommended)?|requires|required|supports)\s*[-:]\s*")
+        .expect("Invalid requirement prefix regex")
+});
+
+// "X (Optional)"
+static OPTIONAL_SUFFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\s*\(optional\)$").expect("Invalid requirement suffix regex")
+});
+
+/// Values authors put in the requirements list to mean "nothing".
+const PLACEHOLDERS: [&str; 5] = ["none", "n/a", "na", "-", "nothing"];
+
+/// A single entry of `ModVersion::requirements`.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone)]
+pub(crate) struct Requirement {
+    pub(crate) name: String,
+    pub(crate) optional: bool,
+    /// Catalog id taken from a `/mods/download/<id>` link, if the entry had one.
+    pub(crate) mod_id: Option<usize>,
+    pub(crate) raw: String,
+}
+
+impl Requirement {
+    /// Parses a raw requirement such as `BTKUILib` or `[Requires - BTKUILib](url)`.
+    ///
+    /// Returns `None` for empty entries and placeholders like `None` or `N/A`.
+    pub(crate) fn parse(raw: &str) -> Option<Self> {
+        let text = raw.trim().trim_matches('"').trim();
+
+        let (name, url) = match MARKDOWN_LINK.captures(text) {
+            Some(captures) => (
+                captures["name"].trim().to_string(),
+                Some(captures["url"].trim().to_string()),
+            ),
+            None => (text.to_string(), None),
+        };
+
+        let mut optional = false;
+        let mut name = name;
+
+        if let Some(captures) = NAME_PREFIX.captures(&name) {
+            let kind = captures["kind"].to_lowercase();
+            optional = kind.starts_with("optional") || kind == "supports";
+            name = name[captures.get(0).map_or(0, |m| m.end())..].to_string();
+        }
+
+        if OPTIONAL_SUFFIX.is_match(&name) {
+            optional = true;
+            name = OPTIONAL_SUFFIX.replace(&name, "").to_string();
+        }
+
+        let name = name.trim().to_string();
+        if is_placeholder(&name) {
+            return None;
+        }
+
+        let mod_id = url
+            .as_deref()
+            .and_then(|url| MOD_ID_IN_LINK.captures(url))
+            .and_then(|captures| captures[1].parse().ok());
+
+        Some(Self {
+            name,
+            optional,
+            mod_id,
+            raw: raw.to_string(),
+        })
+    }
+}
+
+fn is_placeholder(name: &str) -> bool {
+    let name = name.trim_end_matches(['!', '.']).trim();
+    name.is_empty()
+        || PLACEHOLDERS
+            .iter()
+            .any(|placeholder| name.eq_ignore_ascii_case(placeholder))
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.optional {
+            write!(f, "{} (optional)", self.name)
+        } else {
+            write!(f, "{}", self.name)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Requirement;
+
+    #[test]
+    fn parses_catalog_requirements() {
+        // (raw, name, optional, mod id)
+        let cases: [(&str, &str, bool, Option<usize>); 12] = [
+            ("BTKUILib", "BTKUILib", false, None),
+            ("Lag Free Screenshots", "Lag Free Screenshots", false, None),
+            (
+                "[BTKUILib](https://api.cvrmg.com/v1/mods/download/113)",
+                "BTKUILib",
+                false,
+                Some(113),
+            ),
+            (
+                "\"[BTKUILib](https://api.cvrmg.com/v1/mods/download/113)\"",
+                "BTKUILib",
+                false,
+                Some(113),
+            ),
+            (
+                "[BTKUILib](https://api.cvrmg.com/v1/mods/files/113/6/BTKUILib.dll)",
+                "BTKUILib",
+                false,
+                Some(113),
+            ),
+            (
+                "[BTKUILib](https://github.com/BTK-Development/BTKUILib)",
+                "BTKUILib",
+                false,
+                None,
+            ),
+            (
+                "[Requires - UIExpansionKit](https://api.cvrmg.com/v1/mods/download/90)",
+                "UIExpansionKit",
+                false,
+                Some(90),
+            ),
+            (
+                "[Optional but recommended - Chatbox](https://api.cvrmg.com/v1/mods/download/168)",
+                "Chatbox",
+                true,
+                Some(168),
+            ),
+            (
+                "[Supports - BTKUILib](https://api.cvrmg.com/v1/mods/download/113)",
+                "BTKUILib",
+                true,
+                Some(113),
+            ),
+            (
+                "[ChatBox (Optional)](https://github.com/kafeijao/Kafe_CVR_Mods/tree/master/ChatBox)",
+                "ChatBox",
+                true,
+                None,
+            ),
+            (
+                "[Action Menu](https://github.com/dakyneko/DakyModsCVR)",
+                "Action Menu",
+                false,
+                None,
+            ),
+            ("  playerctl  ", "playerctl", false, None),
+        ];
+
+        for (raw, name, optional, mod_id) in cases {
+            let requirement =
+                Requirement::parse(raw).unwrap_or_else(|| panic!("{raw} was dropped"));
+            assert_eq!(requirement.name, name, "{raw}");
+            assert_eq!(requirement.optional, optional, "{raw}");
+            assert_eq!(requirement.mod_id, mod_id, "{raw}");
+            assert_eq!(requirement.raw, raw);
+        }
+    }
+
+    #[test]
+    fn drops_placeholders() {
+        for raw in [
+            "",
+            "   ",
+            "None",
+            "None!",
+            "N/A",
+            "n/a",
+            "-",
+            "\"\"",
+            "[None](https://x)",
+        ] {
+            assert_eq!(Requirement::parse(raw), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn displays_optional_requirements() {
+        let requirement = Requirement::parse("[ChatBox (Optional)](https://x)").unwrap();
+        assert_eq!(requirement.to_string(), "ChatBox (optional)");
+        assert_eq!(
+            Requirement::parse("BTKUILib").unwrap().to_string(),
+            "BTKUILib"
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use super::api_error::ApiError;
+use crate::config::CVRMelonConfig;
+use std::{
+    collections::hash_map::RandomState,
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Upper bound for a server provided `Retry-After`, so a bad header cannot stall us for hours.
+// `from_mins` needs a newer toolchain than the one we support
+#[allow(clippy::duration_suboptimal_units)]
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+/// How failed requests are retried.
+#[derive(Debug, Clone)]
+pub(crate) struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    pub(crate) base_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub(crate) fn from_config(config: &CVRMelonConfig) -> Self {
+        Self {
+            max_retries: config.max_retries(),
+            base_delay: config.retry_base_delay(),
+        }
+    }
+
+    /// Runs `operation` until it succeeds, fails with an error that is not worth retrying,
+    /// or runs out of retries.
+    pub(crate) async fn run<T, F, Fut>(&self, operation: F) -> Result<T, ApiError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        self.run_with_fallback(false, operation).await
+    }
+
+    /// Like `run`, except that a server that cannot be reached at all is given up on right
+    /// away when `has_fallback`, trying the next mirror beats waiting for this one.
+    pub(crate) async fn run_with_fallback<T, F, Fut>(
+        &self,
+        has_fallback: bool,
+        mut operation: F,
+    ) -> Result<T, ApiError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        let mut retry = 0;
+
+        loop {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(err) if has_fallback && err.is_connect() => return Err(err),
+                Err(err) if retry < self.max_retries && err.is_retryable() => {
+                    let delay = self.delay(&err, retry);
+                    retry += 1;
+
+                    eprintln!(
+                        "{err}, retrying in {:.1}s ({retry}/{})",
+                        delay.as_secs_f32(),
+                        self.max_retries
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// How long to wait before retrying after `err`, the server's `Retry-After` if it sent
+    /// one and the backoff for `retry` otherwise.
+    fn delay(&self, err: &ApiError, retry: u32) -> Duration {
+        err.retry_after()
+            .map_or_else(|| self.backoff(retry), |delay| delay.min(MAX_RETRY_AFTER))
+    }
+
+    /// Exponential backoff, randomized so parallel downloads do not retry in lockstep.
+    fn backoff(&self, retry: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(MAX_BACKOFF);
+
+        ceiling.mul_f64(jitter())
+    }
+}
+
+/// A random factor in `0.5..1.0`, `RandomState` is seeded randomly every time it is created.
+fn jitter() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+
+    #[allow(clippy::cast_precision_loss)]
+    let fraction = (random >> 11) as f64 / (1u64 << 53) as f64;
+    0.5 + fraction / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RetryPolicy, MAX_BACKOFF, MAX_RETRY_AFTER};
+    use crate::api::api_error::ApiError;
+    use std::{
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+
+    fn status(status: u16, retry_after: Option<Duration>) -> ApiError {
+        ApiError::HttpStatus {
+            url: "https://api.cvrmg.com/v1/mods/".to_string(),
+            status,
+            retry_after,
+        }
+    }
+
+    #[test]
+    fn backs_off_exponentially_up_to_the_cap() {
+        let policy = RetryPolicy {
+            max_retries: 20,
+            base_delay: Duration::from_secs(1),
+        };
+
+        // (retry, ceiling before jitter)
+        let cases: [(u32, Duration); 5] = [
+            (0, Duration::from_secs(1)),
+            (1, Duration::from_secs(2)),
+            (3, Duration::from_secs(8)),
+            (5, MAX_BACKOFF),
+            (u32::MAX, MAX_BACKOFF),
+        ];
+
+        for (retry, ceiling) in cases {
+            for _ in 0..20 {
+                let delay = policy.backoff(retry);
+                assert!(
+                    delay >= ceiling / 2 && delay <= ceiling,
+                    "{retry}: {delay:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::duration_suboptimal_units)]
+    fn waits_as_long_as_the_server_asks_within_limits() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+        };
+
+        let asked = Duration::from_secs(7);
+        assert_eq!(policy.delay(&status(429, Some(asked)), 0), asked);
+        assert_eq!(policy.delay(&status(503, Some(asked)), 2), asked);
+        assert_eq!(
+            policy.delay(&status(429, Some(Duration::from_secs(6 * 60 * 60))), 0),
+            MAX_RETRY_AFTER
+        );
+
+        // Without a usable Retry-After the backoff applies
+        for err in [status(500, Some(asked)), status(429, None)] {
+            let delay = policy.delay(&err, 0);
+            assert!(delay <= Duration::from_millis(100), "{err}: {delay:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_unreachable_servers_with_a_fallback() {
+        let policy = RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+        };
+        // Nothing listens on a port that was just released
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = reqwest::Client::new();
+
+        // (another mirror follows, attempts)
+        for (has_fallback, attempts) in [(true, 1), (false, 3)] {
+            let made = AtomicU32::new(0);
+            let result: Result<(), ApiError> = policy
+                .run_with_fallback(has_fallback, || async {
+                    made.fetch_add(1, Ordering::Relaxed);
+                    client.get(format!("http://{address}/")).send().await?;
+                    Ok(())
+                })
+                .await;
+
+            assert!(result.is_err_and(|err| err.is_connect()));
+            assert_eq!(made.into_inner(), attempts, "fallback: {has_fallback}");
+        }
+    }
+}
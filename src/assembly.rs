@@ -0,0 +1,1518 @@
+//! Reads the metadata of .NET assemblies (ECMA-335) to identify mod DLLs without loading them.
+
+use crate::api::{
+    api_error::ApiError,
+    loader_version::{self, LoaderRequirement},
+    mod_info::ModInfo,
+    mod_version::{self, ModType, ModVersion},
+};
+use semver::Version;
+use std::path::Path;
+
+#[cfg(test)]
+pub(crate) mod test_assembly;
+
+/// Data directory holding the CLI header.
+const CLI_HEADER_DIRECTORY: usize = 14;
+const METADATA_SIGNATURE: u32 = 0x424A_5342;
+
+// Metadata tables, ECMA-335 II.22
+const TYPE_REF: usize = 0x01;
+const TYPE_DEF: usize = 0x02;
+const FIELD: usize = 0x04;
+const METHOD_DEF: usize = 0x06;
+const PARAM: usize = 0x08;
+const MEMBER_REF: usize = 0x0A;
+const CUSTOM_ATTRIBUTE: usize = 0x0C;
+const EVENT: usize = 0x14;
+const PROPERTY: usize = 0x17;
+const MODULE_REF: usize = 0x1A;
+const TYPE_SPEC: usize = 0x1B;
+const ASSEMBLY: usize = 0x20;
+const ASSEMBLY_REF: usize = 0x23;
+const FILE: usize = 0x26;
+const EXPORTED_TYPE: usize = 0x27;
+const GENERIC_PARAM: usize = 0x2A;
+/// Tables past `GenericParamConstraint` only appear in portable PDBs.
+const TABLE_COUNT: usize = 0x2D;
+
+/// Longest chain of base classes followed when looking for `MelonMod`/`MelonPlugin`.
+const MAX_BASE_TYPE_DEPTH: usize = 16;
+/// Arrays and boxed values in attribute blobs nested deeper than this are rejected.
+const MAX_BLOB_NESTING: usize = 16;
+
+/// `MelonInfo` attribute of a mod or plugin.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct MelonInfo {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    pub(crate) author: Option<String>,
+    pub(crate) download_link: Option<String>,
+    /// Full name of the class deriving from `MelonMod` or `MelonPlugin`.
+    pub(crate) type_name: Option<String>,
+}
+
+/// `MelonGame` attribute, `None` fields match any developer or game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MelonGame {
+    pub(crate) developer: Option<String>,
+    pub(crate) name: Option<String>,
+}
+
+/// What an assembly's metadata says about it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AssemblyInfo {
+    pub(crate) assembly_name: Option<String>,
+    pub(crate) assembly_version: Option<[u16; 4]>,
+    pub(crate) melon_info: Option<MelonInfo>,
+    pub(crate) melon_games: Vec<MelonGame>,
+    /// Whether the `MelonInfo` class derives from `MelonMod` or `MelonPlugin`.
+    pub(crate) mod_type: Option<ModType>,
+    /// The `MelonLoader` version named by `VerifyLoaderVersion`.
+    pub(crate) loader_version: Option<LoaderRequirement>,
+}
+
+impl AssemblyInfo {
+    /// Whether the assembly targets ChilloutVR, or does not restrict the game at all.
+    pub(crate) fn targets_chillout(&self) -> bool {
+        self.melon_games.is_empty()
+            || self.melon_games.iter().any(|game| {
+                game.name
+                    .as_deref()
+                    .is_none_or(|name| name.eq_ignore_ascii_case("ChilloutVR"))
+            })
+    }
+}
+
+pub(crate) async fn read_assembly_info_from_file(path: &Path) -> Result<AssemblyInfo, ApiError> {
+    let data = tokio::fs::read(path).await?;
+    read_assembly_info(&data)
+}
+
+/// Parses the PE image in `data` and extracts its assembly identity and `MelonLoader`
+/// attributes.
+pub(crate) fn read_assembly_info(data: &[u8]) -> Result<AssemblyInfo, ApiError> {
+    let metadata = Metadata::parse(metadata_root(data)?)?;
+    let mut info = AssemblyInfo::default();
+
+    if metadata.row_counts[ASSEMBLY] > 0 {
+        let version = [1, 2, 3, 4].map(|column| {
+            metadata
+                .read(ASSEMBLY, 1, column)
+                .map_or(0, |value| u16::try_from(value).unwrap_or_default())
+        });
+        info.assembly_version = Some(version);
+        info.assembly_name = Some(metadata.string(metadata.read(ASSEMBLY, 1, 7)?)?);
+    }
+
+    for row in 1..=metadata.row_counts[CUSTOM_ATTRIBUTE] {
+        // Attributes we cannot decode are not the ones we are looking for
+        let Ok(Some((name, args))) = metadata.custom_attribute(row) else {
+            continue;
+        };
+
+        match name.as_str() {
+            "MelonInfoAttribute" => info.melon_info = melon_info_from_args(&args),
+            "MelonModInfoAttribute" => {
+                info.melon_info = melon_info_from_args(&args);
+                info.mod_type = Some(ModType::Mod);
+            }
+            "MelonPluginInfoAttribute" => {
+                info.melon_info = melon_info_from_args(&args);
+                info.mod_type = Some(ModType::Plugin);
+            }
+            "MelonGameAttribute" | "MelonModGameAttribute" | "MelonPluginGameAttribute" => {
+                let strings = string_args(&args);
+                info.melon_games.push(MelonGame {
+                    developer: strings.first().cloned().flatten(),
+                    name: strings.get(1).cloned().flatten(),
+                });
+            }
+            "VerifyLoaderVersionAttribute" => {
+                info.loader_version = loader_requirement_from_args(&args);
+            }
+            _ => {}
+        }
+    }
+
+    if info.mod_type.is_none() {
+        info.mod_type = info
+            .melon_info
+            .as_ref()
+            .and_then(|melon_info| melon_info.type_name.as_deref())
+            .and_then(|type_name| metadata.melon_base_type(type_name));
+    }
+
+    Ok(info)
+}
+
+/// Best catalog match for an assembly by the name, author and version in its `MelonInfo`,
+/// falling back to the assembly name. `None` if the assembly is made for another game, or
+/// if no mod or more than one mod fits equally well.
+pub(crate) fn match_catalog<'a>(
+    mods: &'a [ModInfo],
+    info: &AssemblyInfo,
+) -> Option<(&'a ModInfo, Option<&'a ModVersion>)> {
+    // The catalog only lists ChilloutVR mods, a namesake for another game is not one of them
+    if !info.targets_chillout() {
+        return None;
+    }
+
+    let names: Vec<String> = info
+        .melon_info
+        .iter()
+        .map(|melon_info| normalize_name(&melon_info.name))
+        .chain(info.assembly_name.as_deref().map(normalize_name))
+        .filter(|name| !name.is_empty())
+        .collect();
+    let author = info
+        .melon_info
+        .as_ref()
+        .and_then(|melon_info| melon_info.author.as_deref())
+        .map(normalize_name);
+
+    let mut best: Option<(&ModInfo, u8)> = None;
+    let mut tied = false;
+
+    for mod_info in mods {
+        let score = name_score(mod_info, &names) + author_score(mod_info, author.as_deref());
+        if score <= 1 {
+            continue;
+        }
+
+        match best {
+            Some((_, best_score)) if best_score > score => {}
+            Some((_, best_score)) if best_score == score => tied = true,
+            _ => {
+                best = Some((mod_info, score));
+                tied = false;
+            }
+        }
+    }
+
+    let (mod_info, _) = best.filter(|_| !tied)?;
+    let version = info
+        .melon_info
+        .as_ref()
+        .and_then(|melon_info| find_version(mod_info, &melon_info.version));
+
+    Some((mod_info, version))
+}
+
+/// `3` for an exact name or alias match, `2` when one name contains the other.
+fn name_score(mod_info: &ModInfo, names: &[String]) -> u8 {
+    let candidates: Vec<String> = std::iter::once(&mod_info.name)
+        .chain(mod_info.aliases.iter().flatten())
+        .map(|name| normalize_name(name))
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    let mut score = 0;
+    for name in names {
+        for candidate in &candidates {
+            if name == candidate {
+                return 3;
+            }
+
+            let (shorter, longer) = if name.len() < candidate.len() {
+                (name, candidate)
+            } else {
+                (candidate, name)
+            };
+            if shorter.len() >= 4 && longer.contains(shorter.as_str()) {
+                score = 2;
+            }
+        }
+    }
+
+    score
+}
+
+fn author_score(mod_info: &ModInfo, author: Option<&str>) -> u8 {
+    let Some(author) = author.filter(|author| !author.is_empty()) else {
+        return 0;
+    };
+
+    let matches = mod_info
+        .versions
+        .iter()
+        .flat_map(|version| version.authors.iter())
+        .any(|name| {
+            let name = normalize_name(name);
+            !name.is_empty() && (author.contains(name.as_str()) || name.contains(author))
+        });
+
+    u8::from(matches)
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Catalog version equal to `version`, ignoring pre-release tags if nothing matches exactly.
+fn find_version<'a>(mod_info: &'a ModInfo, version: &str) -> Option<&'a ModVersion> {
+    let version = version.trim().trim_start_matches(['v', 'V']);
+    let version = semver::Version::parse(&mod_version::normalize_version(version)).ok()?;
+
+    mod_info
+        .versions
+        .iter()
+        .find(|candidate| candidate.mod_version == version)
+        .or_else(|| {
+            mod_info.versions.iter().find(|candidate| {
+                let candidate = &candidate.mod_version;
+                (candidate.major, candidate.minor, candidate.patch)
+                    == (version.major, version.minor, version.patch)
+            })
+        })
+}
+
+/// Decoded fixed argument of a custom attribute.
+#[derive(Debug, Clone)]
+enum AttributeValue {
+    Bool(bool),
+    Int(i64),
+    String(Option<String>),
+    Type(Option<String>),
+    Array(Vec<AttributeValue>),
+    /// Floating point values, never used by the attributes we read.
+    Other,
+}
+
+fn string_args(args: &[AttributeValue]) -> Vec<Option<String>> {
+    args.iter()
+        .filter_map(|arg| match arg {
+            AttributeValue::String(value) => Some(value.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn int_args(args: &[AttributeValue]) -> Vec<i64> {
+    args.iter()
+        .filter_map(|arg| match arg {
+            AttributeValue::Int(value) => Some(*value),
+            _ => None,
+        })
+        .collect()
+}
+
+fn bool_args(args: &[AttributeValue]) -> Vec<bool> {
+    args.iter()
+        .filter_map(|arg| match arg {
+            AttributeValue::Bool(value) => Some(*value),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Handles every `VerifyLoaderVersion` constructor:
+///
+/// * `(semver)` and `(semver, isMinimum)`
+/// * `(major, minor, patch)` and `(major, minor, patch, isMinimum)`
+fn loader_requirement_from_args(args: &[AttributeValue]) -> Option<LoaderRequirement> {
+    let is_minimum = bool_args(args).first().copied().unwrap_or(false);
+    let ints = int_args(args);
+
+    let version = if let [major, minor, patch, ..] = ints[..] {
+        Version::new(
+            u64::try_from(major).ok()?,
+            u64::try_from(minor).ok()?,
+            u64::try_from(patch).ok()?,
+        )
+    } else {
+        let semver = string_args(args).into_iter().next().flatten()?;
+        loader_version::parse_loader_version(&semver)?
+    };
+
+    Some(LoaderRequirement::from_attribute(version, is_minimum))
+}
+
+/// Handles every `MelonInfo` constructor:
+///
+/// * `(Type, name, version, author, downloadLink)`
+/// * `(Type, name, major, minor, patch, author, downloadLink)`
+/// * `(Type, name, major, minor, patch, identifier, author, downloadLink)`
+fn melon_info_from_args(args: &[AttributeValue]) -> Option<MelonInfo> {
+    let type_name = args.iter().find_map(|arg| match arg {
+        AttributeValue::Type(type_name) => type_name.clone(),
+        _ => None,
+    });
+    let strings = string_args(args);
+    let ints = int_args(args);
+
+    let name = strings.first().cloned().flatten()?;
+    let (version, rest) = if ints.is_empty() {
+        (strings.get(1).cloned().flatten()?, strings.get(2..))
+    } else {
+        let mut version = ints
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
+
+        if strings.len() >= 4 {
+            if let Some(identifier) = strings[1].as_deref().filter(|id| !id.is_empty()) {
+                version = format!("{version}-{identifier}");
+            }
+            (version, strings.get(2..))
+        } else {
+            (version, strings.get(1..))
+        }
+    };
+    let rest = rest.unwrap_or_default();
+
+    Some(MelonInfo {
+        name,
+        version,
+        author: rest.first().cloned().flatten(),
+        download_link: rest.get(1).cloned().flatten(),
+        type_name: type_name.map(|type_name| strip_assembly_qualifier(&type_name).to_string()),
+    })
+}
+
+/// `Namespace.Type, Assembly, Version=...` to `Namespace.Type`.
+fn strip_assembly_qualifier(type_name: &str) -> &str {
+    type_name.split(',').next().unwrap_or(type_name).trim()
+}
+
+/// `VS_FIXEDFILEINFO::dwSignature`.
+const FIXED_FILE_INFO_SIGNATURE: u32 = 0xFEEF_04BD;
+
+/// File version from the version resource of a PE image, native DLLs included.
+///
+/// Rather than walking the resource directory, this looks for the `VS_FIXEDFILEINFO`
+/// signature, which is DWORD aligned and followed by a fixed structure version.
+pub(crate) fn read_file_version(data: &[u8]) -> Option<[u16; 4]> {
+    if data.get(..2) != Some(b"MZ") {
+        return None;
+    }
+
+    (0..data.len().saturating_sub(16))
+        .step_by(4)
+        .filter(|&offset| read_u32(data, offset).ok() == Some(FIXED_FILE_INFO_SIGNATURE))
+        .filter(|&offset| read_u32(data, offset + 4).ok() == Some(0x0001_0000))
+        .find_map(|offset| {
+            let most = read_u32(data, offset + 8).ok()?;
+            let least = read_u32(data, offset + 12).ok()?;
+            Some(
+                [most >> 16, most & 0xFFFF, least >> 16, least & 0xFFFF]
+                    .map(|part| u16::try_from(part).unwrap_or_default()),
+            )
+        })
+}
+
+fn invalid(reason: &str) -> ApiError {
+    ApiError::InvalidAssembly(reason.to_string())
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, ApiError> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| invalid("unexpected end of file"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ApiError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| invalid("unexpected end of file"))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, ApiError> {
+    Ok(u64::from(read_u32(data, offset)?) | (u64::from(read_u32(data, offset + 4)?) << 32))
+}
+
+fn to_usize(value: u32) -> usize {
+    usize::try_from(value).unwrap_or(usize::MAX)
+}
+
+/// A PE section, used to map RVAs to file offsets.
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+    raw_size: u32,
+}
+
+/// Locates the CLI metadata root (`BSJB`) inside a PE image.
+fn metadata_root(data: &[u8]) -> Result<&[u8], ApiError> {
+    if data.get(..2) != Some(b"MZ") {
+        return Err(invalid("missing MZ header"));
+    }
+
+    let pe_offset = to_usize(read_u32(data, 0x3C)?);
+    if data.get(pe_offset..pe_offset + 4) != Some(b"PE\0\0") {
+        return Err(invalid("missing PE signature"));
+    }
+
+    let coff = pe_offset + 4;
+    let section_count = usize::from(read_u16(data, coff + 2)?);
+    let optional_header = coff + 20;
+    let optional_header_size = usize::from(read_u16(data, coff + 16)?);
+
+    let (directory_count_offset, directories) = match read_u16(data, optional_header)? {
+        0x10B => (optional_header + 92, optional_header + 96),
+        0x20B => (optional_header + 108, optional_header + 112),
+        _ => return Err(invalid("unknown optional header")),
+    };
+    if to_usize(read_u32(data, directory_count_offset)?) <= CLI_HEADER_DIRECTORY {
+        return Err(invalid("no CLI header"));
+    }
+
+    let cli_header_rva = read_u32(data, directories + CLI_HEADER_DIRECTORY * 8)?;
+    if cli_header_rva == 0 {
+        return Err(invalid("no CLI header"));
+    }
+
+    let section_table = optional_header + optional_header_size;
+    let sections = (0..section_count)
+        .map(|index| {
+            let header = section_table + index * 40;
+            Ok(Section {
+                virtual_size: read_u32(data, header + 8)?,
+                virtual_address: read_u32(data, header + 12)?,
+                raw_size: read_u32(data, header + 16)?,
+                raw_offset: read_u32(data, header + 20)?,
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let rva_to_offset = |rva: u32| {
+        sections
+            .iter()
+            .find(|section| {
+                let size = section.virtual_size.max(section.raw_size);
+                rva >= section.virtual_address && rva - section.virtual_address < size
+            })
+            .ok_or_else(|| invalid("RVA outside of every section"))
+            .and_then(|section| {
+                (rva - section.virtual_address)
+                    .checked_add(section.raw_offset)
+                    .map(to_usize)
+                    .ok_or_else(|| invalid("section outside of the file"))
+            })
+    };
+
+    let cli_header = rva_to_offset(cli_header_rva)?;
+    let metadata_rva = read_u32(data, cli_header + 8)?;
+    let metadata_size = to_usize(read_u32(data, cli_header + 12)?);
+    let metadata = rva_to_offset(metadata_rva)?;
+
+    let root = data
+        .get(metadata..metadata.saturating_add(metadata_size))
+        .ok_or_else(|| invalid("metadata outside of the file"))?;
+    if read_u32(root, 0)? != METADATA_SIGNATURE {
+        return Err(invalid("bad metadata signature"));
+    }
+
+    Ok(root)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CodedIndex {
+    TypeDefOrRef,
+    HasConstant,
+    HasCustomAttribute,
+    HasFieldMarshal,
+    HasDeclSecurity,
+    MemberRefParent,
+    HasSemantics,
+    MethodDefOrRef,
+    MemberForwarded,
+    Implementation,
+    CustomAttributeType,
+    ResolutionScope,
+    TypeOrMethodDef,
+}
+
+impl CodedIndex {
+    /// Tables selected by each tag value, `None` for tags that are reserved.
+    fn tables(self) -> &'static [Option<usize>] {
+        match self {
+            Self::TypeDefOrRef => &[Some(TYPE_DEF), Some(TYPE_REF), Some(TYPE_SPEC)],
+            Self::HasConstant => &[Some(FIELD), Some(PARAM), Some(PROPERTY)],
+            Self::HasCustomAttribute => &[
+                Some(METHOD_DEF),
+                Some(FIELD),
+                Some(TYPE_REF),
+                Some(TYPE_DEF),
+                Some(PARAM),
+                Some(0x09),
+                Some(MEMBER_REF),
+                Some(0x00),
+                Some(0x0E),
+                Some(PROPERTY),
+                Some(EVENT),
+                Some(0x11),
+                Some(MODULE_REF),
+                Some(TYPE_SPEC),
+                Some(ASSEMBLY),
+                Some(ASSEMBLY_REF),
+                Some(FILE),
+                Some(EXPORTED_TYPE),
+                Some(0x28),
+                Some(GENERIC_PARAM),
+                Some(0x2C),
+                Some(0x2B),
+            ],
+            Self::HasFieldMarshal => &[Some(FIELD), Some(PARAM)],
+            Self::HasDeclSecurity => &[Some(TYPE_DEF), Some(METHOD_DEF), Some(ASSEMBLY)],
+            Self::MemberRefParent => &[
+                Some(TYPE_DEF),
+                Some(TYPE_REF),
+                Some(MODULE_REF),
+                Some(METHOD_DEF),
+                Some(TYPE_SPEC),
+            ],
+            Self::HasSemantics => &[Some(EVENT), Some(PROPERTY)],
+            Self::MethodDefOrRef => &[Some(METHOD_DEF), Some(MEMBER_REF)],
+            Self::MemberForwarded => &[Some(FIELD), Some(METHOD_DEF)],
+            Self::Implementation => &[Some(FILE), Some(ASSEMBLY_REF), Some(EXPORTED_TYPE)],
+            Self::CustomAttributeType => &[None, None, Some(METHOD_DEF), Some(MEMBER_REF), None],
+            Self::ResolutionScope => &[
+                Some(0x00),
+                Some(MODULE_REF),
+                Some(ASSEMBLY_REF),
+                Some(TYPE_REF),
+            ],
+            Self::TypeOrMethodDef => &[Some(TYPE_DEF), Some(METHOD_DEF)],
+        }
+    }
+
+    fn tag_bits(self) -> u32 {
+        usize::BITS - (self.tables().len() - 1).leading_zeros()
+    }
+
+    /// Splits a coded index into its table and 1-based row, `None` for reserved tags.
+    fn decode(self, value: u32) -> Option<(usize, u32)> {
+        let bits = self.tag_bits();
+        let tag = to_usize(value & ((1 << bits) - 1));
+        let table = (*self.tables().get(tag)?)?;
+        Some((table, value >> bits))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Column {
+    U16,
+    U32,
+    String,
+    Guid,
+    Blob,
+    Table(usize),
+    Coded(CodedIndex),
+}
+
+/// Columns of every table, ECMA-335 II.22.
+#[allow(clippy::too_many_lines)]
+fn table_schema(table: usize) -> &'static [Column] {
+    use CodedIndex as Ci;
+    use Column as C;
+
+    match table {
+        0x00 => &[C::U16, C::String, C::Guid, C::Guid, C::Guid],
+        0x01 => &[C::Coded(Ci::ResolutionScope), C::String, C::String],
+        0x02 => &[
+            C::U32,
+            C::String,
+            C::String,
+            C::Coded(Ci::TypeDefOrRef),
+            C::Table(FIELD),
+            C::Table(METHOD_DEF),
+        ],
+        0x03 => &[C::Table(FIELD)],
+        0x04 | 0x17 => &[C::U16, C::String, C::Blob],
+        0x05 => &[C::Table(METHOD_DEF)],
+        0x06 => &[C::U32, C::U16, C::U16, C::String, C::Blob, C::Table(PARAM)],
+        0x07 => &[C::Table(PARAM)],
+        0x08 => &[C::U16, C::U16, C::String],
+        0x09 => &[C::Table(TYPE_DEF), C::Coded(Ci::TypeDefOrRef)],
+        0x0A => &[C::Coded(Ci::MemberRefParent), C::String, C::Blob],
+        0x0B => &[C::U16, C::Coded(Ci::HasConstant), C::Blob],
+        0x0C => &[
+            C::Coded(Ci::HasCustomAttribute),
+            C::Coded(Ci::CustomAttributeType),
+            C::Blob,
+        ],
+        0x0D => &[C::Coded(Ci::HasFieldMarshal), C::Blob],
+        0x0E => &[C::U16, C::Coded(Ci::HasDeclSecurity), C::Blob],
+        0x0F => &[C::U16, C::U32, C::Table(TYPE_DEF)],
+        0x10 | 0x1D => &[C::U32, C::Table(FIELD)],
+        0x11 | 0x1B => &[C::Blob],
+        0x12 => &[C::Table(TYPE_DEF), C::Table(EVENT)],
+        0x13 => &[C::Table(EVENT)],
+        0x14 => &[C::U16, C::String, C::Coded(Ci::TypeDefOrRef)],
+        0x15 => &[C::Table(TYPE_DEF), C::Table(PROPERTY)],
+        0x16 => &[C::Table(PROPERTY)],
+        0x18 => &[C::U16, C::Table(METHOD_DEF), C::Coded(Ci::HasSemantics)],
+        0x19 => &[
+            C::Table(TYPE_DEF),
+            C::Coded(Ci::MethodDefOrRef),
+            C::Coded(Ci::MethodDefOrRef),
+        ],
+        0x1A => &[C::String],
+        0x1C => &[
+            C::U16,
+            C::Coded(Ci::MemberForwarded),
+            C::String,
+            C::Table(MODULE_REF),
+        ],
+        0x1E => &[C::U32, C::U32],
+        0x1F | 0x21 => &[C::U32],
+        0x20 => &[
+            C::U32,
+            C::U16,
+            C::U16,
+            C::U16,
+            C::U16,
+            C::U32,
+            C::Blob,
+            C::String,
+            C::String,
+        ],
+        0x22 => &[C::U32, C::U32, C::U32],
+        0x23 => &[
+            C::U16,
+            C::U16,
+            C::U16,
+            C::U16,
+            C::U32,
+            C::Blob,
+            C::String,
+            C::String,
+            C::Blob,
+        ],
+        0x24 => &[C::U32, C::Table(ASSEMBLY_REF)],
+        0x25 => &[C::U32, C::U32, C::U32, C::Table(ASSEMBLY_REF)],
+        0x26 => &[C::U32, C::String, C::Blob],
+        0x27 => &[
+            C::U32,
+            C::U32,
+            C::String,
+            C::String,
+            C::Coded(Ci::Implementation),
+        ],
+        0x28 => &[C::U32, C::U32, C::String, C::Coded(Ci::Implementation)],
+        0x29 => &[C::Table(TYPE_DEF), C::Table(TYPE_DEF)],
+        0x2A => &[C::U16, C::U16, C::Coded(Ci::TypeOrMethodDef), C::String],
+        0x2B => &[C::Coded(Ci::MethodDefOrRef), C::Blob],
+        0x2C => &[C::Table(GENERIC_PARAM), C::Coded(Ci::TypeDefOrRef)],
+        _ => &[],
+    }
+}
+
+/// The `#~` tables stream and the heaps its rows point into.
+struct Metadata<'a> {
+    tables: &'a [u8],
+    strings: &'a [u8],
+    blobs: &'a [u8],
+    row_counts: [u32; TABLE_COUNT],
+    table_offsets: [usize; TABLE_COUNT],
+    row_sizes: [usize; TABLE_COUNT],
+    wide_strings: bool,
+    wide_guids: bool,
+    wide_blobs: bool,
+}
+
+impl<'a> Metadata<'a> {
+    fn parse(root: &'a [u8]) -> Result<Self, ApiError> {
+        let version_length = to_usize(read_u32(root, 12)?);
+        let stream_count = read_u16(root, 16 + version_length + 2)?;
+
+        let mut tables = None;
+        let mut strings: &[u8] = &[];
+        let mut blobs: &[u8] = &[];
+
+        let mut header = 16 + version_length + 4;
+        for _ in 0..stream_count {
+            let offset = to_usize(read_u32(root, header)?);
+            let size = to_usize(read_u32(root, header + 4)?);
+            let name_start = header + 8;
+            let name_length = root
+                .get(name_start..)
+                .and_then(|rest| rest.iter().position(|&byte| byte == 0))
+                .ok_or_else(|| invalid("unterminated stream name"))?;
+            let name = &root[name_start..name_start + name_length];
+            // Names are null terminated and padded to four bytes
+            header = name_start + (name_length + 4) / 4 * 4;
+
+            let stream = root
+                .get(offset..offset.saturating_add(size))
+                .ok_or_else(|| invalid("stream outside of the metadata"))?;
+            match name {
+                b"#~" | b"#-" => tables = Some(stream),
+                b"#Strings" => strings = stream,
+                b"#Blob" => blobs = stream,
+                _ => {}
+            }
+        }
+
+        let tables = tables.ok_or_else(|| invalid("no metadata tables"))?;
+        let heap_sizes = *tables.get(6).ok_or_else(|| invalid("truncated tables"))?;
+        let valid = read_u64(tables, 8)?;
+        if valid >> TABLE_COUNT != 0 {
+            return Err(invalid("unsupported metadata tables"));
+        }
+
+        let mut metadata = Self {
+            tables,
+            strings,
+            blobs,
+            row_counts: [0; TABLE_COUNT],
+            table_offsets: [0; TABLE_COUNT],
+            row_sizes: [0; TABLE_COUNT],
+            wide_strings: heap_sizes & 0x01 != 0,
+            wide_guids: heap_sizes & 0x02 != 0,
+            wide_blobs: heap_sizes & 0x04 != 0,
+        };
+
+        let mut offset = 24;
+        for table in 0..TABLE_COUNT {
+            if valid & (1 << table) != 0 {
+                metadata.row_counts[table] = read_u32(tables, offset)?;
+                offset += 4;
+            }
+        }
+        // Edit-and-continue images have an extra field after the row counts
+        if heap_sizes & 0x40 != 0 {
+            offset += 4;
+        }
+
+        for table in 0..TABLE_COUNT {
+            let row_size = table_schema(table)
+                .iter()
+                .map(|&column| metadata.column_size(column))
+                .sum();
+            metadata.row_sizes[table] = row_size;
+            metadata.table_offsets[table] = offset;
+            offset += row_size * to_usize(metadata.row_counts[table]);
+        }
+
+        if offset > tables.len() {
+            return Err(invalid("truncated tables"));
+        }
+
+        Ok(metadata)
+    }
+
+    fn column_size(&self, column: Column) -> usize {
+        let index_size = |wide: bool| if wide { 4 } else { 2 };
+
+        match column {
+            Column::U16 => 2,
+            Column::U32 => 4,
+            Column::String => index_size(self.wide_strings),
+            Column::Guid => index_size(self.wide_guids),
+            Column::Blob => index_size(self.wide_blobs),
+            Column::Table(table) => index_size(self.row_counts[table] > 0xFFFF),
+            Column::Coded(coded) => {
+                let max_rows = coded
+                    .tables()
+                    .iter()
+                    .flatten()
+                    .map(|&table| self.row_counts[table])
+                    .max()
+                    .unwrap_or(0);
+                index_size(max_rows >= 1 << (16 - coded.tag_bits()))
+            }
+        }
+    }
+
+    /// Reads `column` of the 1-based `row` in `table`.
+    fn read(&self, table: usize, row: u32, column: usize) -> Result<u32, ApiError> {
+        if row == 0 || row > self.row_counts[table] {
+            return Err(invalid("row index out of range"));
+        }
+
+        let schema = table_schema(table);
+        let offset = self.table_offsets[table]
+            + self.row_sizes[table] * to_usize(row - 1)
+            + schema[..column]
+                .iter()
+                .map(|&column| self.column_size(column))
+                .sum::<usize>();
+
+        if self.column_size(schema[column]) == 2 {
+            read_u16(self.tables, offset).map(u32::from)
+        } else {
+            read_u32(self.tables, offset)
+        }
+    }
+
+    fn string(&self, index: u32) -> Result<String, ApiError> {
+        let rest = self
+            .strings
+            .get(to_usize(index)..)
+            .ok_or_else(|| invalid("string outside of the heap"))?;
+        let end = rest
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(rest.len());
+        Ok(String::from_utf8_lossy(&rest[..end]).into_owned())
+    }
+
+    fn blob(&self, index: u32) -> Result<&'a [u8], ApiError> {
+        let mut reader = BlobReader::new(
+            self.blobs
+                .get(to_usize(index)..)
+                .ok_or_else(|| invalid("blob outside of the heap"))?,
+        );
+        let length = to_usize(reader.compressed()?);
+        reader.bytes(length)
+    }
+
+    /// Namespace and name of a `TypeRef` or `TypeDef` row.
+    fn type_name(&self, table: usize, row: u32) -> Result<(String, String), ApiError> {
+        match table {
+            TYPE_REF => Ok((
+                self.string(self.read(TYPE_REF, row, 2)?)?,
+                self.string(self.read(TYPE_REF, row, 1)?)?,
+            )),
+            TYPE_DEF => Ok((
+                self.string(self.read(TYPE_DEF, row, 2)?)?,
+                self.string(self.read(TYPE_DEF, row, 1)?)?,
+            )),
+            _ => Err(invalid("not a named type")),
+        }
+    }
+
+    /// Attribute type name and fixed arguments of a `CustomAttribute` row.
+    ///
+    /// `None` if the constructor is defined in this assembly rather than referenced.
+    fn custom_attribute(
+        &self,
+        row: u32,
+    ) -> Result<Option<(String, Vec<AttributeValue>)>, ApiError> {
+        let constructor = self.read(CUSTOM_ATTRIBUTE, row, 1)?;
+        let Some((MEMBER_REF, member)) = CodedIndex::CustomAttributeType.decode(constructor) else {
+            return Ok(None);
+        };
+
+        let parent = self.read(MEMBER_REF, member, 0)?;
+        let Some((parent_table, parent_row)) = CodedIndex::MemberRefParent.decode(parent) else {
+            return Ok(None);
+        };
+        if parent_table != TYPE_REF && parent_table != TYPE_DEF {
+            return Ok(None);
+        }
+        let (_, name) = self.type_name(parent_table, parent_row)?;
+
+        let parameters = parse_method_signature(self.blob(self.read(MEMBER_REF, member, 2)?)?)?;
+        let mut value = BlobReader::new(self.blob(self.read(CUSTOM_ATTRIBUTE, row, 2)?)?);
+        if value.u16()? != 0x0001 {
+            return Err(invalid("bad custom attribute prolog"));
+        }
+
+        let args = parameters
+            .iter()
+            .map(|parameter| value.value(parameter))
+            .collect::<Result<Vec<_>, ApiError>>()?;
+
+        Ok(Some((name, args)))
+    }
+
+    /// Follows the base classes of `type_name` until it reaches `MelonMod` or `MelonPlugin`.
+    fn melon_base_type(&self, type_name: &str) -> Option<ModType> {
+        let (namespace, name) = type_name.rsplit_once('.').unwrap_or(("", type_name));
+        let mut row = (1..=self.row_counts[TYPE_DEF]).find(|&row| {
+            self.type_name(TYPE_DEF, row)
+                .is_ok_and(|(ns, n)| ns == namespace && n == name)
+        })?;
+
+        for _ in 0..MAX_BASE_TYPE_DEPTH {
+            let extends = self.read(TYPE_DEF, row, 3).ok()?;
+            let (table, base) = CodedIndex::TypeDefOrRef.decode(extends)?;
+            let (_, base_name) = self.type_name(table, base).ok()?;
+
+            match base_name.as_str() {
+                "MelonMod" => return Some(ModType::Mod),
+                "MelonPlugin" => return Some(ModType::Plugin),
+                _ if table == TYPE_DEF => row = base,
+                _ => return None,
+            }
+        }
+
+        None
+    }
+}
+
+/// Parameter types of a method signature that may appear in a custom attribute constructor.
+#[derive(Debug, Clone)]
+enum ParamType {
+    Bool,
+    Char,
+    Int(usize, bool),
+    Float(usize),
+    String,
+    Type,
+    /// Underlying type unknown without resolving the enum, `int` is by far the most common.
+    Enum,
+    Object,
+    Array(Box<ParamType>),
+}
+
+fn parse_method_signature(signature: &[u8]) -> Result<Vec<ParamType>, ApiError> {
+    let mut reader = BlobReader::new(signature);
+    let calling_convention = reader.u8()?;
+    // Generic methods carry their type parameter count first
+    if calling_convention & 0x10 != 0 {
+        reader.compressed()?;
+    }
+
+    let count = reader.compressed()?;
+    if reader.u8()? != 0x01 {
+        return Err(invalid("constructor does not return void"));
+    }
+
+    (0..count).map(|_| reader.param_type()).collect()
+}
+
+struct BlobReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> BlobReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    /// Runs `read` one level deeper, so crafted blobs cannot recurse without end.
+    fn nested<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, ApiError>,
+    ) -> Result<T, ApiError> {
+        if self.depth >= MAX_BLOB_NESTING {
+            return Err(invalid("blob nested too deeply"));
+        }
+
+        self.depth += 1;
+        let result = read(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], ApiError> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.saturating_add(length))
+            .ok_or_else(|| invalid("blob ended early"))?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, ApiError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ApiError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, ApiError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Compressed unsigned integer, ECMA-335 II.23.2.
+    fn compressed(&mut self) -> Result<u32, ApiError> {
+        let first = self.u8()?;
+        if first & 0x80 == 0 {
+            Ok(u32::from(first))
+        } else if first & 0xC0 == 0x80 {
+            Ok((u32::from(first & 0x3F) << 8) | u32::from(self.u8()?))
+        } else if first & 0xE0 == 0xC0 {
+            let rest = self.bytes(3)?;
+            Ok((u32::from(first & 0x1F) << 24)
+                | (u32::from(rest[0]) << 16)
+                | (u32::from(rest[1]) << 8)
+                | u32::from(rest[2]))
+        } else {
+            Err(invalid("bad compressed integer"))
+        }
+    }
+
+    /// Length-prefixed UTF-8 string, `0xFF` encodes `null`.
+    fn ser_string(&mut self) -> Result<Option<String>, ApiError> {
+        if self.data.get(self.position) == Some(&0xFF) {
+            self.position += 1;
+            return Ok(None);
+        }
+
+        let length = to_usize(self.compressed()?);
+        Ok(Some(
+            String::from_utf8_lossy(self.bytes(length)?).into_owned(),
+        ))
+    }
+
+    fn param_type(&mut self) -> Result<ParamType, ApiError> {
+        let element = self.u8()?;
+        match element {
+            // Custom modifiers, skip the modifier type
+            0x1F | 0x20 => {
+                self.compressed()?;
+                self.nested(Self::param_type)
+            }
+            // Any class in an attribute constructor is System.Type
+            0x12 => {
+                self.compressed()?;
+                Ok(ParamType::Type)
+            }
+            0x11 => {
+                self.compressed()?;
+                Ok(ParamType::Enum)
+            }
+            0x1D => Ok(ParamType::Array(Box::new(self.nested(Self::param_type)?))),
+            element => element_type(element),
+        }
+    }
+
+    /// Element type tag of a boxed value or array in a custom attribute blob.
+    fn field_type(&mut self) -> Result<ParamType, ApiError> {
+        match self.u8()? {
+            0x50 => Ok(ParamType::Type),
+            0x51 => Ok(ParamType::Object),
+            0x55 => {
+                self.ser_string()?;
+                Ok(ParamType::Enum)
+            }
+            0x1D => Ok(ParamType::Array(Box::new(self.nested(Self::field_type)?))),
+            element => element_type(element),
+        }
+    }
+
+    fn value(&mut self, param_type: &ParamType) -> Result<AttributeValue, ApiError> {
+        Ok(match param_type {
+            ParamType::Bool => AttributeValue::Bool(self.u8()? != 0),
+            ParamType::Char => AttributeValue::Int(i64::from(self.u16()?)),
+            ParamType::Int(size, signed) => {
+                let bytes = self.bytes(*size)?;
+                let mut buffer = [0; 8];
+                buffer[..*size].copy_from_slice(bytes);
+                let unsigned = u64::from_le_bytes(buffer);
+                // Sign extend from the original width
+                let shift = 64 - 8 * size;
+                #[allow(clippy::cast_possible_wrap)]
+                let value = if *signed {
+                    ((unsigned << shift) as i64) >> shift
+                } else {
+                    unsigned as i64
+                };
+                AttributeValue::Int(value)
+            }
+            ParamType::Float(size) => {
+                self.bytes(*size)?;
+                AttributeValue::Other
+            }
+            ParamType::String => AttributeValue::String(self.ser_string()?),
+            ParamType::Type => AttributeValue::Type(self.ser_string()?),
+            ParamType::Enum => AttributeValue::Int(i64::from(self.u32()?)),
+            ParamType::Object => {
+                let boxed = self.nested(Self::field_type)?;
+                self.nested(|reader| reader.value(&boxed))?
+            }
+            ParamType::Array(element) => {
+                let count = self.u32()?;
+                if count == u32::MAX {
+                    AttributeValue::Array(Vec::new())
+                } else {
+                    AttributeValue::Array(
+                        (0..count)
+                            .map(|_| self.nested(|reader| reader.value(element)))
+                            .collect::<Result<_, _>>()?,
+                    )
+                }
+            }
+        })
+    }
+}
+
+fn element_type(element: u8) -> Result<ParamType, ApiError> {
+    Ok(match element {
+        0x02 => ParamType::Bool,
+        0x03 => ParamType::Char,
+        0x04 => ParamType::Int(1, true),
+        0x05 => ParamType::Int(1, false),
+        0x06 => ParamType::Int(2, true),
+        0x07 => ParamType::Int(2, false),
+        0x08 => ParamType::Int(4, true),
+        0x09 => ParamType::Int(4, false),
+        0x0A => ParamType::Int(8, true),
+        0x0B => ParamType::Int(8, false),
+        0x0C => ParamType::Float(4),
+        0x0D => ParamType::Float(8),
+        0x0E => ParamType::String,
+        0x1C => ParamType::Object,
+        _ => return Err(invalid("unsupported attribute argument type")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_assembly::{
+        build_assembly, u32_bytes, TestAssembly, VerifyLoader, CLI_DIRECTORY_OFFSET,
+        SECTION_OFFSET, SECTION_RAW_OFFSET_OFFSET, SECTION_RVA,
+    };
+    use super::{
+        author_score, match_catalog, name_score, normalize_name, read_assembly_info,
+        read_file_version, AssemblyInfo, BlobReader, MelonGame, MelonInfo,
+        FIXED_FILE_INFO_SIGNATURE,
+    };
+    use crate::api::{api_error::ApiError, mod_info::test_mod, mod_version::ModType};
+    use semver::Version;
+
+    fn invalid_reason(data: &[u8]) -> String {
+        match read_assembly_info(data) {
+            Err(ApiError::InvalidAssembly(reason)) => reason,
+            other => panic!("expected an invalid assembly, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reads_melon_info() {
+        let info = read_assembly_info(&build_assembly(&TestAssembly::default())).unwrap();
+
+        assert_eq!(info.assembly_name.as_deref(), Some("TestMod"));
+        assert_eq!(info.assembly_version, Some([1, 2, 3, 4]));
+        assert_eq!(
+            info.melon_info,
+            Some(MelonInfo {
+                name: "Test Mod".to_string(),
+                version: "1.0.0".to_string(),
+                author: Some("Someone".to_string()),
+                download_link: None,
+                type_name: Some("TestMod.Mod".to_string()),
+            })
+        );
+        assert_eq!(
+            info.melon_games,
+            [MelonGame {
+                developer: Some("Alpha Blend Interactive".to_string()),
+                name: Some("ChilloutVR".to_string()),
+            }]
+        );
+        assert!(info.targets_chillout());
+        assert_eq!(info.mod_type, Some(ModType::Mod));
+        assert_eq!(info.loader_version, None);
+    }
+
+    #[test]
+    fn reads_verify_loader_version() {
+        // (attribute, displayed, installed versions that match, ones that do not)
+        let cases: [(VerifyLoader, &str, &[&str], &[&str]); 5] = [
+            (
+                VerifyLoader::Semver("0.6.1", Some(true)),
+                ">=0.6.1",
+                &["0.6.1", "0.7.0"],
+                &["0.6.0"],
+            ),
+            (
+                VerifyLoader::Semver("v0.5.4", Some(false)),
+                "=0.5.4",
+                &["0.5.4"],
+                &["0.5.3", "0.5.5"],
+            ),
+            (
+                VerifyLoader::Semver("0.5.7", None),
+                "=0.5.7",
+                &["0.5.7"],
+                &["0.6.0"],
+            ),
+            (
+                VerifyLoader::Parts(0, 6, 0, Some(true)),
+                ">=0.6.0",
+                &["0.6.0", "1.0.0"],
+                &["0.5.7"],
+            ),
+            (
+                VerifyLoader::Parts(0, 5, 7, None),
+                "=0.5.7",
+                &["0.5.7"],
+                &["0.5.6", "0.5.8"],
+            ),
+        ];
+
+        for (verify_loader, displayed, matching, not_matching) in cases {
+            let image = build_assembly(&TestAssembly {
+                verify_loader: Some(verify_loader),
+                ..TestAssembly::default()
+            });
+            let info = read_assembly_info(&image).unwrap();
+            let requirement = info.loader_version.expect("VerifyLoaderVersion is read");
+
+            assert_eq!(requirement.to_string(), displayed, "{verify_loader:?}");
+            for installed in matching {
+                let installed = Version::parse(installed).unwrap();
+                assert!(
+                    requirement.matches(&installed),
+                    "{displayed} with {installed}"
+                );
+            }
+            for installed in not_matching {
+                let installed = Version::parse(installed).unwrap();
+                assert!(
+                    !requirement.matches(&installed),
+                    "{displayed} with {installed}"
+                );
+            }
+            // The other attributes are still read
+            assert_eq!(info.melon_info.unwrap().name, "Test Mod");
+        }
+    }
+
+    #[test]
+    fn reads_the_plugin_type() {
+        let image = build_assembly(&TestAssembly {
+            base_type: "MelonPlugin",
+            ..TestAssembly::default()
+        });
+
+        assert_eq!(
+            read_assembly_info(&image).unwrap().mod_type,
+            Some(ModType::Plugin)
+        );
+    }
+
+    /// A PE image with a `VS_FIXEDFILEINFO` at `offset`.
+    fn version_resource(offset: usize, structure_version: u32, version: [u16; 4]) -> Vec<u8> {
+        let mut image = b"MZ".to_vec();
+        image.resize(offset, 0);
+        image.extend_from_slice(&u32_bytes(FIXED_FILE_INFO_SIGNATURE));
+        image.extend_from_slice(&u32_bytes(structure_version));
+        let [major, minor, build, revision] = version.map(u32::from);
+        image.extend_from_slice(&u32_bytes(major << 16 | minor));
+        image.extend_from_slice(&u32_bytes(build << 16 | revision));
+        image.resize(image.len() + 32, 0);
+        image
+    }
+
+    #[test]
+    fn reads_the_file_version() {
+        let version = [0, 6, 1, 0];
+
+        assert_eq!(
+            read_file_version(&version_resource(0x100, 0x0001_0000, version)),
+            Some(version)
+        );
+        assert_eq!(
+            read_file_version(&version_resource(0x104, 0x0001_0000, [1, 2, 3, 4])),
+            Some([1, 2, 3, 4])
+        );
+        // The signature is DWORD aligned and followed by the structure version
+        assert_eq!(
+            read_file_version(&version_resource(0x102, 0x0001_0000, version)),
+            None
+        );
+        assert_eq!(
+            read_file_version(&version_resource(0x100, 0x0002_0000, version)),
+            None
+        );
+
+        let mut not_pe = version_resource(0x100, 0x0001_0000, version);
+        not_pe[..2].copy_from_slice(b"PK");
+        assert_eq!(read_file_version(&not_pe), None);
+        assert_eq!(read_file_version(b"MZ"), None);
+        assert_eq!(
+            read_file_version(&build_assembly(&TestAssembly::default())),
+            None
+        );
+    }
+
+    #[test]
+    fn scores_near_miss_names() {
+        let mut ui = test_mod(113, "BTKUILib", "2.0.0", &[]);
+        ui.aliases = Some(vec!["BTK UI Lib".to_string()]);
+
+        // (name from the assembly, score)
+        let cases: [(&str, u8); 8] = [
+            ("BTKUILib", 3),
+            ("btk_ui_lib", 3),
+            ("BTK.UI.Lib", 3),
+            ("BTKUILib.Fork", 2),
+            ("UILib", 2),
+            // Too short to count as contained
+            ("BTK", 0),
+            ("BTKUILab", 0),
+            ("", 0),
+        ];
+
+        for (name, score) in cases {
+            let names = [normalize_name(name)];
+            assert_eq!(name_score(&ui, &names), score, "{name:?}");
+        }
+    }
+
+    #[test]
+    fn scores_author_spellings() {
+        let mut ui = test_mod(113, "BTKUILib", "2.0.0", &[]);
+        ui.versions[0].authors = vec!["BTK Development".to_string(), "DDAkebono".to_string()];
+
+        // (author from the MelonInfo, score)
+        let cases: [(Option<&str>, u8); 7] = [
+            (Some("BTK Development"), 1),
+            (Some("btk-development"), 1),
+            (Some("DDAkebono & BTK Development"), 1),
+            (Some("ddakebono"), 1),
+            (Some("Someone Else"), 0),
+            (Some("  "), 0),
+            (None, 0),
+        ];
+
+        for (author, score) in cases {
+            let author = author.map(normalize_name);
+            assert_eq!(author_score(&ui, author.as_deref()), score, "{author:?}");
+        }
+    }
+
+    #[test]
+    fn matches_near_miss_names_by_author() {
+        let mut custom = test_mod(81, "Custom Nameplates", "1.0.0", &[]);
+        custom.versions[0].authors = vec!["Penny".to_string()];
+        let mut nocturnal = test_mod(21, "Nocturnal Nameplates", "1.0.0", &[]);
+        nocturnal.versions[0].authors = vec!["Nocturnal".to_string()];
+        let mods = [custom, nocturnal];
+
+        // (MelonInfo name, author, matched id)
+        let cases: [(&str, Option<&str>, Option<usize>); 5] = [
+            ("Custom Nameplates", None, Some(81)),
+            ("CustomNameplates", Some("Someone Else"), Some(81)),
+            // Both contain the name, only the author tells them apart
+            ("Nameplates", None, None),
+            ("Nameplates", Some("penny"), Some(81)),
+            ("Nameplates", Some("Nocturnal#1234"), Some(21)),
+        ];
+
+        for (name, author, id) in cases {
+            let info = AssemblyInfo {
+                melon_info: Some(MelonInfo {
+                    name: name.to_string(),
+                    version: "1.0.0".to_string(),
+                    author: author.map(str::to_string),
+                    ..MelonInfo::default()
+                }),
+                ..AssemblyInfo::default()
+            };
+
+            assert_eq!(
+                match_catalog(&mods, &info).map(|(mod_info, _)| mod_info.id),
+                id,
+                "{name:?} by {author:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let image = build_assembly(&TestAssembly::default());
+
+        for length in [0, 0x40, 0x100, SECTION_OFFSET + 80, image.len() - 8] {
+            invalid_reason(&image[..length]);
+        }
+    }
+
+    #[test]
+    fn rejects_rvas_outside_the_file() {
+        let mut image = build_assembly(&TestAssembly::default());
+        image[CLI_DIRECTORY_OFFSET..CLI_DIRECTORY_OFFSET + 4]
+            .copy_from_slice(&u32_bytes(0x9000_0000));
+        assert_eq!(invalid_reason(&image), "RVA outside of every section");
+
+        // Inside the section, but the section's file offset plus the distance overflows
+        let mut image = build_assembly(&TestAssembly::default());
+        image[CLI_DIRECTORY_OFFSET..CLI_DIRECTORY_OFFSET + 4]
+            .copy_from_slice(&u32_bytes(SECTION_RVA + 0x40));
+        image[SECTION_RAW_OFFSET_OFFSET..SECTION_RAW_OFFSET_OFFSET + 4]
+            .copy_from_slice(&u32_bytes(u32::MAX - 16));
+        assert_eq!(invalid_reason(&image), "section outside of the file");
+    }
+
+    #[test]
+    fn rejects_non_pe_files() {
+        assert_eq!(invalid_reason(b""), "missing MZ header");
+        assert_eq!(invalid_reason(b"PK\x03\x04 not a dll"), "missing MZ header");
+
+        let mut image = build_assembly(&TestAssembly::default());
+        image[0x80..0x84].copy_from_slice(b"NE\0\0");
+        assert_eq!(invalid_reason(&image), "missing PE signature");
+    }
+
+    #[test]
+    fn limits_blob_nesting() {
+        // Arrays of arrays of ... of int
+        let mut signature = vec![0x1D; 64];
+        signature.push(0x08);
+        assert!(BlobReader::new(&signature).param_type().is_err());
+
+        let mut shallow = vec![0x1D; 4];
+        shallow.push(0x08);
+        assert!(BlobReader::new(&shallow).param_type().is_ok());
+
+        // Objects boxing objects
+        let boxed = [0x51; 64];
+        assert!(BlobReader::new(&boxed)
+            .value(&super::ParamType::Object)
+            .is_err());
+    }
+
+    #[test]
+    fn matches_only_chillout_assemblies() {
+        let mods = [
+            test_mod(113, "BTKUILib", "2.0.0", &[]),
+            test_mod(90, "UI Expansion Kit", "1.0.0", &[]),
+        ];
+        let game = |developer: Option<&str>, name: Option<&str>| MelonGame {
+            developer: developer.map(str::to_string),
+            name: name.map(str::to_string),
+        };
+
+        // (MelonGame attributes, matched id)
+        let cases: [(Vec<MelonGame>, Option<usize>); 5] = [
+            (Vec::new(), Some(113)),
+            (
+                vec![game(Some("Alpha Blend Interactive"), Some("ChilloutVR"))],
+                Some(113),
+            ),
+            (vec![game(Some("Alpha Blend Interactive"), None)], Some(113)),
+            (vec![game(Some("VRChat"), Some("VRChat"))], None),
+            (
+                vec![
+                    game(Some("VRChat"), Some("VRChat")),
+                    game(None, Some("chilloutvr")),
+                ],
+                Some(113),
+            ),
+        ];
+
+        for (melon_games, id) in cases {
+            let info = AssemblyInfo {
+                melon_info: Some(MelonInfo {
+                    name: "BTKUILib".to_string(),
+                    version: "2.0.0".to_string(),
+                    ..MelonInfo::default()
+                }),
+                melon_games,
+                ..AssemblyInfo::default()
+            };
+
+            let matched = match_catalog(&mods, &info);
+            assert_eq!(
+                matched.map(|(mod_info, _)| mod_info.id),
+                id,
+                "{:?}",
+                info.melon_games
+            );
+            if let Some((_, version)) = matched {
+                assert_eq!(version, mods[0].latest_version());
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! Builds minimal .NET assemblies for tests, so no real mod DLLs need to be checked in.
+
+pub(crate) const SECTION_RVA: u32 = 0x2000;
+pub(crate) const SECTION_OFFSET: usize = 0x200;
+/// File offsets inside the image built by `build_assembly`.
+pub(crate) const CLI_DIRECTORY_OFFSET: usize = 0x168;
+pub(crate) const SECTION_RAW_OFFSET_OFFSET: usize = 0x18C;
+
+/// Arguments of a `VerifyLoaderVersion` attribute, with the optional `IsMinimum` flag.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum VerifyLoader<'a> {
+    Semver(&'a str, Option<bool>),
+    Parts(i32, i32, i32, Option<bool>),
+}
+
+/// What `build_assembly` puts into the image.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TestAssembly<'a> {
+    /// Assembly name, also the namespace of the mod class.
+    pub(crate) assembly_name: &'a str,
+    pub(crate) name: &'a str,
+    pub(crate) version: &'a str,
+    pub(crate) author: Option<&'a str>,
+    /// `MelonMod` or `MelonPlugin`.
+    pub(crate) base_type: &'a str,
+    pub(crate) verify_loader: Option<VerifyLoader<'a>>,
+}
+
+impl Default for TestAssembly<'_> {
+    fn default() -> Self {
+        Self {
+            assembly_name: "TestMod",
+            name: "Test Mod",
+            version: "1.0.0",
+            author: Some("Someone"),
+            base_type: "MelonMod",
+            verify_loader: None,
+        }
+    }
+}
+
+fn u16_bytes(value: u16) -> [u8; 2] {
+    value.to_le_bytes()
+}
+
+pub(crate) fn u32_bytes(value: u32) -> [u8; 4] {
+    value.to_le_bytes()
+}
+
+fn ser_string(value: Option<&str>) -> Vec<u8> {
+    let Some(value) = value else {
+        return vec![0xFF];
+    };
+    let mut bytes = vec![u8::try_from(value.len()).unwrap()];
+    bytes.extend_from_slice(value.as_bytes());
+    bytes
+}
+
+#[derive(Default)]
+struct Heaps {
+    strings: Vec<u8>,
+    blobs: Vec<u8>,
+}
+
+impl Heaps {
+    fn string(&mut self, value: &str) -> u16 {
+        let index = u16::try_from(self.strings.len()).unwrap();
+        self.strings.extend_from_slice(value.as_bytes());
+        self.strings.push(0);
+        index
+    }
+
+    fn blob(&mut self, value: &[u8]) -> u16 {
+        let index = u16::try_from(self.blobs.len()).unwrap();
+        self.blobs.push(u8::try_from(value.len()).unwrap());
+        self.blobs.extend_from_slice(value);
+        index
+    }
+}
+
+fn pad(bytes: &mut Vec<u8>) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(0);
+    }
+}
+
+/// A custom attribute on the assembly.
+struct Attribute {
+    type_name: &'static str,
+    signature: Vec<u8>,
+    value: Vec<u8>,
+}
+
+impl Attribute {
+    /// `parameters` are the encoded types of the constructor parameters, `arguments` the
+    /// encoded values.
+    fn new(type_name: &'static str, parameters: &[&[u8]], arguments: &[u8]) -> Self {
+        let mut signature = vec![0x20, u8::try_from(parameters.len()).unwrap(), 0x01];
+        signature.extend(parameters.concat());
+
+        // Prolog, the arguments and no named arguments
+        let mut value = vec![0x01, 0x00];
+        value.extend_from_slice(arguments);
+        value.extend_from_slice(&[0, 0]);
+
+        Self {
+            type_name,
+            signature,
+            value,
+        }
+    }
+}
+
+fn attributes(assembly: &TestAssembly) -> Vec<Attribute> {
+    let mut info = ser_string(Some(&format!("{}.Mod", assembly.assembly_name)));
+    for value in [
+        Some(assembly.name),
+        Some(assembly.version),
+        assembly.author,
+        None,
+    ] {
+        info.extend(ser_string(value));
+    }
+    let mut game = ser_string(Some("Alpha Blend Interactive"));
+    game.extend(ser_string(Some("ChilloutVR")));
+
+    let mut attributes = vec![
+        // A class parameter is followed by its TypeDefOrRef token
+        Attribute::new(
+            "MelonInfoAttribute",
+            &[&[0x12, 0x09], &[0x0E], &[0x0E], &[0x0E], &[0x0E]],
+            &info,
+        ),
+        Attribute::new("MelonGameAttribute", &[&[0x0E], &[0x0E]], &game),
+    ];
+
+    let (mut parameters, mut arguments, is_minimum) = match assembly.verify_loader {
+        None => return attributes,
+        Some(VerifyLoader::Semver(semver, is_minimum)) => {
+            (vec![&[0x0E][..]], ser_string(Some(semver)), is_minimum)
+        }
+        Some(VerifyLoader::Parts(major, minor, patch, is_minimum)) => (
+            vec![&[0x08][..]; 3],
+            [major, minor, patch]
+                .iter()
+                .flat_map(|part| part.to_le_bytes())
+                .collect(),
+            is_minimum,
+        ),
+    };
+    if let Some(is_minimum) = is_minimum {
+        parameters.push(&[0x02]);
+        arguments.push(u8::from(is_minimum));
+    }
+    attributes.push(Attribute::new(
+        "VerifyLoaderVersionAttribute",
+        &parameters,
+        &arguments,
+    ));
+
+    attributes
+}
+
+/// Builds the smallest PE image `read_assembly_info` accepts: an assembly with a class
+/// deriving from `base_type`, a `MelonInfo`, a `MelonGame` for ChilloutVR and optionally a
+/// `VerifyLoaderVersion` attribute. The assembly version is always 1.2.3.4.
+pub(crate) fn build_assembly(assembly: &TestAssembly) -> Vec<u8> {
+    let mut heaps = Heaps {
+        strings: vec![0],
+        blobs: vec![0],
+    };
+    let mut tables = build_tables(assembly, &mut heaps);
+
+    pad(&mut tables);
+    pad(&mut heaps.strings);
+    pad(&mut heaps.blobs);
+
+    build_image(&build_metadata_root(&tables, &heaps))
+}
+
+fn build_tables(assembly: &TestAssembly, heaps: &mut Heaps) -> Vec<u8> {
+    let attributes = attributes(assembly);
+    let attribute_count = u16::try_from(attributes.len()).unwrap();
+
+    let mut tables = Vec::new();
+    tables.extend_from_slice(&u32_bytes(0));
+    tables.extend_from_slice(&[2, 0, 0, 1]);
+    // Module, TypeRef, TypeDef, MemberRef, CustomAttribute and Assembly
+    let valid: u64 = 1 | 1 << 0x01 | 1 << 0x02 | 1 << 0x0A | 1 << 0x0C | 1 << 0x20;
+    tables.extend_from_slice(&valid.to_le_bytes());
+    tables.extend_from_slice(&0_u64.to_le_bytes());
+    for rows in [
+        1,
+        attribute_count + 1,
+        2,
+        attribute_count,
+        attribute_count,
+        1,
+    ] {
+        tables.extend_from_slice(&u32_bytes(u32::from(rows)));
+    }
+
+    // Module
+    let module_name = heaps.string(&format!("{}.dll", assembly.assembly_name));
+    for column in [0, module_name, 0, 0, 0] {
+        tables.extend_from_slice(&u16_bytes(column));
+    }
+
+    // TypeRef, the base class first and the attribute types after it
+    let namespace = heaps.string("MelonLoader");
+    let type_names =
+        std::iter::once(assembly.base_type).chain(attributes.iter().map(|a| a.type_name));
+    for name in type_names {
+        let name = heaps.string(name);
+        for column in [0, name, namespace] {
+            tables.extend_from_slice(&u16_bytes(column));
+        }
+    }
+
+    // TypeDef, `<Module>` and the mod class deriving from TypeRef 1
+    let module_type = heaps.string("<Module>");
+    let class_name = heaps.string("Mod");
+    let class_namespace = heaps.string(assembly.assembly_name);
+    for (flags, name, namespace, extends) in [
+        (0, module_type, 0, 0),
+        (0x0010_0001, class_name, class_namespace, 1 << 2 | 1),
+    ] {
+        tables.extend_from_slice(&u32_bytes(flags));
+        for column in [name, namespace, extends, 1, 1] {
+            tables.extend_from_slice(&u16_bytes(column));
+        }
+    }
+
+    // MemberRef, the attribute constructors
+    let constructor = heaps.string(".ctor");
+    for (row, attribute) in (2..).zip(&attributes) {
+        let signature = heaps.blob(&attribute.signature);
+        for column in [row << 3 | 1, constructor, signature] {
+            tables.extend_from_slice(&u16_bytes(column));
+        }
+    }
+
+    // CustomAttribute, all on the assembly
+    for (row, attribute) in (1..).zip(&attributes) {
+        let value = heaps.blob(&attribute.value);
+        for column in [1 << 5 | 0x0E, row << 3 | 3, value] {
+            tables.extend_from_slice(&u16_bytes(column));
+        }
+    }
+
+    // Assembly
+    let assembly_name = heaps.string(assembly.assembly_name);
+    tables.extend_from_slice(&u32_bytes(0x8004));
+    for column in [1, 2, 3, 4] {
+        tables.extend_from_slice(&u16_bytes(column));
+    }
+    tables.extend_from_slice(&u32_bytes(0));
+    for column in [0, assembly_name, 0] {
+        tables.extend_from_slice(&u16_bytes(column));
+    }
+
+    tables
+}
+
+fn build_metadata_root(tables: &[u8], heaps: &Heaps) -> Vec<u8> {
+    let version = b"v4.0.30319\0\0";
+    let streams: [(&[u8], &[u8]); 3] = [
+        (b"#~\0\0", tables),
+        (b"#Strings\0\0\0\0", &heaps.strings),
+        (b"#Blob\0\0\0", &heaps.blobs),
+    ];
+    let header_length = 20
+        + version.len()
+        + streams
+            .iter()
+            .map(|(name, _)| 8 + name.len())
+            .sum::<usize>();
+
+    let mut root = Vec::new();
+    root.extend_from_slice(&u32_bytes(super::METADATA_SIGNATURE));
+    root.extend_from_slice(&[1, 0, 1, 0, 0, 0, 0, 0]);
+    root.extend_from_slice(&u32_bytes(u32::try_from(version.len()).unwrap()));
+    root.extend_from_slice(version);
+    root.extend_from_slice(&[0, 0, 3, 0]);
+    let mut offset = header_length;
+    for (name, stream) in streams {
+        root.extend_from_slice(&u32_bytes(u32::try_from(offset).unwrap()));
+        root.extend_from_slice(&u32_bytes(u32::try_from(stream.len()).unwrap()));
+        root.extend_from_slice(name);
+        offset += stream.len();
+    }
+    for (_, stream) in streams {
+        root.extend_from_slice(stream);
+    }
+    root
+}
+
+fn build_image(root: &[u8]) -> Vec<u8> {
+    // CLI header directly followed by the metadata
+    let mut section = Vec::new();
+    section.extend_from_slice(&u32_bytes(72));
+    section.extend_from_slice(&[2, 0, 5, 0]);
+    section.extend_from_slice(&u32_bytes(SECTION_RVA + 72));
+    section.extend_from_slice(&u32_bytes(u32::try_from(root.len()).unwrap()));
+    section.resize(72, 0);
+    section.extend_from_slice(root);
+    let section_size = u32::try_from(section.len()).unwrap();
+
+    let mut image = b"MZ".to_vec();
+    image.resize(0x3C, 0);
+    image.extend_from_slice(&u32_bytes(0x80));
+    image.resize(0x80, 0);
+    image.extend_from_slice(b"PE\0\0");
+    image.extend_from_slice(&[0x4C, 0x01, 1, 0]);
+    image.resize(image.len() + 12, 0);
+    image.extend_from_slice(&[224, 0, 0x02, 0x21]);
+
+    // PE32 optional header with 16 data directories, only the CLI header is set
+    let optional_header = image.len();
+    image.extend_from_slice(&u16_bytes(0x10B));
+    image.resize(optional_header + 92, 0);
+    image.extend_from_slice(&u32_bytes(16));
+    image.resize(optional_header + 224, 0);
+    image[CLI_DIRECTORY_OFFSET..CLI_DIRECTORY_OFFSET + 4].copy_from_slice(&u32_bytes(SECTION_RVA));
+    image[CLI_DIRECTORY_OFFSET + 4..CLI_DIRECTORY_OFFSET + 8].copy_from_slice(&u32_bytes(72));
+
+    image.extend_from_slice(b".text\0\0\0");
+    for value in [
+        section_size,
+        SECTION_RVA,
+        section_size,
+        u32::try_from(SECTION_OFFSET).unwrap(),
+    ] {
+        image.extend_from_slice(&u32_bytes(value));
+    }
+    image.resize(SECTION_OFFSET, 0);
+    image.extend(section);
+    image
+}
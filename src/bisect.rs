@@ -0,0 +1,481 @@
+use crate::api::{api_error::ApiError, mod_info::ModInfo, mod_version::ModType};
+use crate::manifest::InstalledMod;
+use crate::{dependencies, disabler, outdated, scanner, utils};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use tokio_util::sync::CancellationToken;
+
+/// How each bisect step finds out whether the problem still happens.
+#[derive(Debug, Clone)]
+pub(crate) enum Check {
+    /// Ask on the terminal, the user launches the game themselves.
+    Prompt,
+    /// Run a shell command, exit code `0` means the problem is gone.
+    Command(String),
+}
+
+impl Check {
+    async fn reproduces(&self, enabled_count: usize) -> Result<bool, ApiError> {
+        match self {
+            Self::Prompt => prompt(enabled_count).await,
+            Self::Command(command) => {
+                let status = shell(command).status().await?;
+                Ok(!status.success())
+            }
+        }
+    }
+}
+
+/// The check command, killed when Ctrl-C drops the running check.
+#[cfg(windows)]
+fn shell(command: &str) -> tokio::process::Command {
+    let mut shell = tokio::process::Command::new("cmd");
+    shell.arg("/C").arg(command).kill_on_drop(true);
+    shell
+}
+
+/// The check command, killed when Ctrl-C drops the running check.
+#[cfg(not(windows))]
+fn shell(command: &str) -> tokio::process::Command {
+    let mut shell = tokio::process::Command::new("sh");
+    shell.arg("-c").arg(command).kill_on_drop(true);
+    shell
+}
+
+async fn prompt(enabled_count: usize) -> Result<bool, ApiError> {
+    utils::confirm(
+        &format!("{enabled_count} mod(s) enabled. Start the game, does the problem still happen?"),
+        None,
+    )
+    .await
+}
+
+/// Something a bisect step switches on or off.
+#[derive(Debug, Clone)]
+pub(crate) enum Candidate {
+    /// A mod from the manifest, or a catalog mod found by hash.
+    Installed(InstalledMod),
+    /// A DLL in `Mods` or `Plugins` that no installed mod accounts for, usually one that
+    /// matches nothing in the catalog. It has no known requirements and is moved without
+    /// touching the manifest.
+    Unidentified {
+        /// Location relative to the ChilloutVR folder.
+        file: PathBuf,
+        location: ModType,
+    },
+}
+
+impl Candidate {
+    pub(crate) fn name(&self) -> String {
+        match self {
+            Self::Installed(installed) => installed.name.clone(),
+            Self::Unidentified { file, .. } => file.display().to_string(),
+        }
+    }
+
+    /// Moves the DLL into or out of the `Disabled` folder and records where it is now.
+    async fn set_enabled(&mut self, chillout_folder: &Path, enabled: bool) -> Result<(), ApiError> {
+        let path = match self {
+            Self::Installed(installed) if enabled => {
+                disabler::enable_mod(chillout_folder, installed).await?
+            }
+            Self::Installed(installed) => disabler::disable_mod(chillout_folder, installed).await?,
+            Self::Unidentified { file, location } => {
+                let path = chillout_folder.join(&*file);
+                if disabler::is_disabled_path(file) != enabled {
+                    return Ok(());
+                }
+
+                let mut folder = chillout_folder.join(location.folder_name());
+                if !enabled {
+                    folder.push(disabler::DISABLED_FOLDER_NAME);
+                }
+                let file_name = path.file_name().ok_or(ApiError::InvalidFileName)?;
+                utils::move_file(&path, &folder.join(file_name)).await?
+            }
+        };
+
+        let file = path
+            .strip_prefix(chillout_folder)
+            .unwrap_or(&path)
+            .to_path_buf();
+        match self {
+            Self::Installed(installed) => installed.file = file,
+            Self::Unidentified { file: current, .. } => *current = file,
+        }
+
+        Ok(())
+    }
+}
+
+/// Result of a bisect run.
+#[derive(Debug, Clone)]
+pub(crate) enum BisectOutcome {
+    /// Enabling this candidate, on top of its requirements and the candidates before it,
+    /// makes the problem appear.
+    Found(Candidate),
+    /// The problem does not happen even with every mod enabled.
+    NotReproducible,
+    /// The problem happens with every mod and DLL disabled, so it is not caused by a mod.
+    NotCausedByMods,
+    /// No enabled mods to bisect.
+    NothingEnabled,
+}
+
+/// Narrows the enabled mods, and the DLLs no installed mod accounts for, down to the one
+/// that causes a problem.
+///
+/// Candidates are ordered so each comes after its requirements and every step enables a
+/// prefix of that order, which keeps the requirements of every enabled mod enabled.
+/// Unidentified DLLs come first, since other mods may need them. The enabled set is halved
+/// until one candidate is left. Every candidate that was enabled before is enabled again
+/// afterwards, also when a step fails or the user presses Ctrl-C.
+pub(crate) async fn bisect(
+    chillout_folder: &Path,
+    mods: &[ModInfo],
+    check: &Check,
+) -> Result<BisectOutcome, ApiError> {
+    let scanned = scanner::scan_mod_folders(chillout_folder, mods).await?;
+    let installed = outdated::collect_scanned(chillout_folder, mods, &scanned).await?;
+    let enabled: Vec<InstalledMod> = installed
+        .into_iter()
+        .filter(|installed| !installed.is_disabled())
+        .collect();
+
+    let tracked: HashSet<PathBuf> = enabled
+        .iter()
+        .map(|installed| installed.path(chillout_folder))
+        .collect();
+    let mut candidates: Vec<Candidate> = scanned
+        .into_iter()
+        .filter(|file| !file.disabled && !tracked.contains(&file.path))
+        .map(|file| Candidate::Unidentified {
+            file: file
+                .path
+                .strip_prefix(chillout_folder)
+                .unwrap_or(&file.path)
+                .to_path_buf(),
+            location: file.location,
+        })
+        .collect();
+    if !candidates.is_empty() {
+        let names: Vec<String> = candidates.iter().map(Candidate::name).collect();
+        println!(
+            "Including {} unidentified DLL(s): {}",
+            names.len(),
+            names.join(", ")
+        );
+    }
+
+    candidates.extend(
+        dependencies::order_installed(mods, &enabled)
+            .into_iter()
+            .map(|installed| Candidate::Installed(installed.clone())),
+    );
+    if candidates.is_empty() {
+        return Ok(BisectOutcome::NothingEnabled);
+    }
+
+    let interrupted = CancellationToken::new();
+    let listener = tokio::spawn({
+        let interrupted = interrupted.clone();
+        async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                interrupted.cancel();
+            }
+        }
+    });
+
+    let result = run(chillout_folder, &mut candidates, check, &interrupted).await;
+    listener.abort();
+    if matches!(result, Err(ApiError::Interrupted)) {
+        println!();
+        println!("Interrupted, enabling every mod again");
+    }
+    let restored = restore(chillout_folder, &mut candidates).await;
+
+    let outcome = result?;
+    restored?;
+    Ok(outcome)
+}
+
+/// Enables every candidate again, carrying on past failures so one stuck file does not
+/// leave the others disabled.
+async fn restore(chillout_folder: &Path, candidates: &mut [Candidate]) -> Result<(), ApiError> {
+    let mut first_error = utils::FirstError::default();
+
+    for candidate in candidates {
+        if let Err(err) = candidate.set_enabled(chillout_folder, true).await {
+            first_error.record(&format!("enable {} again", candidate.name()), err);
+        }
+    }
+
+    first_error.into_result()
+}
+
+async fn run(
+    chillout_folder: &Path,
+    candidates: &mut [Candidate],
+    check: &Check,
+    interrupted: &CancellationToken,
+) -> Result<BisectOutcome, ApiError> {
+    let total = candidates.len();
+
+    // Every mod enabled must be bad and none enabled must be good, or there is nothing to find
+    if !step(chillout_folder, candidates, total, check, interrupted).await? {
+        return Ok(BisectOutcome::NotReproducible);
+    }
+    if step(chillout_folder, candidates, 0, check, interrupted).await? {
+        return Ok(BisectOutcome::NotCausedByMods);
+    }
+
+    let mut search = PrefixSearch::new(total);
+    while let Some(count) = search.next_count() {
+        let reproduces = step(chillout_folder, candidates, count, check, interrupted).await?;
+        search.record(count, reproduces);
+    }
+
+    Ok(BisectOutcome::Found(candidates[search.culprit()].clone()))
+}
+
+/// Binary search for the shortest prefix of the candidates that reproduces the problem,
+/// knowing that no candidates are fine and all of them are not.
+#[derive(Debug, Clone, Copy)]
+struct PrefixSearch {
+    /// The first `good` candidates are fine.
+    good: usize,
+    /// The first `bad` candidates reproduce the problem.
+    bad: usize,
+}
+
+impl PrefixSearch {
+    fn new(total: usize) -> Self {
+        Self {
+            good: 0,
+            bad: total,
+        }
+    }
+
+    /// How many candidates to enable next, `None` once the culprit is known.
+    fn next_count(&self) -> Option<usize> {
+        (self.bad - self.good > 1).then(|| self.good + (self.bad - self.good) / 2)
+    }
+
+    fn record(&mut self, count: usize, reproduces: bool) {
+        if reproduces {
+            self.bad = count;
+        } else {
+            self.good = count;
+        }
+    }
+
+    /// Index of the candidate whose enabling makes the problem appear.
+    fn culprit(&self) -> usize {
+        self.bad - 1
+    }
+}
+
+/// Enables the first `count` candidates, disables the rest and asks `check`.
+///
+/// Moving the files is never cut short by `interrupted`, so every candidate's recorded
+/// location stays true and `restore` can find it. Only waiting for `check` is.
+async fn step(
+    chillout_folder: &Path,
+    candidates: &mut [Candidate],
+    count: usize,
+    check: &Check,
+    interrupted: &CancellationToken,
+) -> Result<bool, ApiError> {
+    apply(chillout_folder, candidates, count).await?;
+    if interrupted.is_cancelled() {
+        return Err(ApiError::Interrupted);
+    }
+
+    let names: Vec<String> = candidates.iter().take(count).map(Candidate::name).collect();
+    println!();
+    println!(
+        "Testing with {} of {} mod(s) enabled",
+        count,
+        candidates.len()
+    );
+    if !names.is_empty() {
+        println!("Enabled: {}", names.join(", "));
+    }
+
+    let reproduces = tokio::select! {
+        reproduces = check.reproduces(count) => reproduces?,
+        () = interrupted.cancelled() => return Err(ApiError::Interrupted),
+    };
+    println!(
+        "Problem {}",
+        if reproduces { "reproduces" } else { "is gone" }
+    );
+
+    Ok(reproduces)
+}
+
+async fn apply(
+    chillout_folder: &Path,
+    candidates: &mut [Candidate],
+    count: usize,
+) -> Result<(), ApiError> {
+    for (index, candidate) in candidates.iter_mut().enumerate() {
+        candidate
+            .set_enabled(chillout_folder, index < count)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bisect, restore, step, BisectOutcome, Candidate, Check, PrefixSearch};
+    use crate::api::{api_error::ApiError, mod_version::ModType};
+    use crate::manifest::{InstalledMod, Manifest};
+    use crate::utils;
+    use semver::Version;
+    use std::path::{Path, PathBuf};
+    use tokio_util::sync::CancellationToken;
+
+    /// Runs the search against a problem caused by the candidate at `culprit`, returning the
+    /// index found and the prefix lengths tested.
+    fn search(total: usize, culprit: usize) -> (usize, Vec<usize>) {
+        let mut search = PrefixSearch::new(total);
+        let mut tested = Vec::new();
+        while let Some(count) = search.next_count() {
+            tested.push(count);
+            search.record(count, count > culprit);
+        }
+        (search.culprit(), tested)
+    }
+
+    #[test]
+    fn finds_every_culprit() {
+        for total in 1..=40 {
+            for culprit in 0..total {
+                let (found, tested) = search(total, culprit);
+                assert_eq!(found, culprit, "{total} candidates");
+
+                // Never more steps than halving needs
+                let max_steps = usize::BITS - (total - 1).leading_zeros();
+                assert!(tested.len() <= max_steps as usize, "{total} candidates");
+                assert!(tested.iter().all(|&count| 0 < count && count < total));
+            }
+        }
+    }
+
+    #[test]
+    fn single_candidate_needs_no_steps() {
+        assert_eq!(search(1, 0), (0, Vec::new()));
+    }
+
+    #[test]
+    fn halves_the_range() {
+        assert_eq!(search(8, 0).1, [4, 2, 1]);
+        assert_eq!(search(8, 7).1, [4, 6, 7]);
+        assert_eq!(search(5, 2).1, [2, 3]);
+    }
+
+    fn enabled_mods(chillout_folder: &Path, names: &[&str]) -> Vec<InstalledMod> {
+        std::fs::create_dir_all(chillout_folder.join("Mods")).unwrap();
+        names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| {
+                let file = PathBuf::from("Mods").join(format!("{name}.dll"));
+                std::fs::write(chillout_folder.join(&file), name).unwrap();
+                InstalledMod {
+                    id,
+                    name: (*name).to_string(),
+                    mod_version: Version::new(1, 0, 0),
+                    mod_type: ModType::Mod,
+                    hash: String::new(),
+                    file,
+                    approval_status: None,
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn interruption_during_apply_finishes_the_moves() {
+        let folder = utils::test_folder("interruption_during_apply_finishes_the_moves");
+        let mut candidates: Vec<Candidate> = enabled_mods(&folder, &["First", "Second", "Third"])
+            .into_iter()
+            .map(Candidate::Installed)
+            .collect();
+
+        // Ctrl-C pressed while the files are being moved
+        let interrupted = CancellationToken::new();
+        interrupted.cancel();
+        let check = Check::Command("exit 0".to_string());
+        let result = step(&folder, &mut candidates, 1, &check, &interrupted).await;
+        assert!(matches!(result, Err(ApiError::Interrupted)));
+
+        // Every recorded location is where the file really is, in memory and in the manifest
+        let manifest = Manifest::load(&folder).await.unwrap();
+        for candidate in &candidates[1..] {
+            let Candidate::Installed(candidate) = candidate else {
+                panic!("expected an installed mod");
+            };
+            assert!(candidate.is_disabled(), "{}", candidate.name);
+            assert!(candidate.path(&folder).exists(), "{}", candidate.name);
+            assert_eq!(manifest.get(candidate.id).unwrap().file, candidate.file);
+        }
+
+        restore(&folder, &mut candidates).await.unwrap();
+        for name in ["First", "Second", "Third"] {
+            assert!(folder.join("Mods").join(format!("{name}.dll")).exists());
+        }
+        assert!(Manifest::load(&folder)
+            .await
+            .unwrap()
+            .installed()
+            .values()
+            .all(|installed| !installed.is_disabled()));
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn finds_unidentified_dlls() {
+        let folder = utils::test_folder("finds_unidentified_dlls");
+        let mut manifest = Manifest::default();
+        for installed in enabled_mods(&folder, &["First", "Second"]) {
+            manifest.insert(installed);
+        }
+        manifest.save(&folder).await.unwrap();
+        std::fs::create_dir_all(folder.join("Plugins")).unwrap();
+        std::fs::write(folder.join("Mods/Unknown.dll"), b"unknown").unwrap();
+        std::fs::write(folder.join("Plugins/Other.dll"), b"other").unwrap();
+
+        // The problem happens while Mods/Unknown.dll is enabled
+        let culprit = folder.join("Mods/Unknown.dll");
+        let check = Check::Command(format!("test ! -f '{}'", culprit.display()));
+        let outcome = bisect(&folder, &[], &check).await.unwrap();
+
+        let BisectOutcome::Found(Candidate::Unidentified { file, location }) = outcome else {
+            panic!("expected an unidentified DLL, got {outcome:?}");
+        };
+        assert_eq!(file, Path::new("Mods/Unknown.dll"));
+        assert_eq!(location, ModType::Mod);
+
+        // Everything is back in place, and the manifest only knows the installed mods
+        for file in [
+            "Mods/Unknown.dll",
+            "Plugins/Other.dll",
+            "Mods/First.dll",
+            "Mods/Second.dll",
+        ] {
+            assert!(folder.join(file).exists(), "{file}");
+        }
+        let manifest = Manifest::load(&folder).await.unwrap();
+        assert_eq!(manifest.installed().len(), 2);
+        assert!(manifest
+            .installed()
+            .values()
+            .all(|installed| !installed.is_disabled()));
+    }
+}
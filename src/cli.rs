@@ -0,0 +1,1335 @@
+use crate::api::{
+    self,
+    api_error::ApiError,
+    mod_info::{self, ModInfo},
+    mod_version::ModVersion,
+    CatalogSource,
+};
+use crate::bisect::{self, BisectOutcome, Candidate};
+use crate::installer::{self, InstallReport};
+use crate::loader_installer::{self, LoaderArchive, StagedLoader};
+use crate::manifest::{self, InstalledMod, Manifest};
+use crate::outdated::{self, OutdatedState};
+use crate::utils::FirstError;
+use crate::{
+    categories, config, dependencies, disabler, game, health, loader, placement, scanner,
+    uninstaller, utils,
+};
+use clap::{Parser, Subcommand};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+/// Manage `MelonLoader` mods for ChilloutVR.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub(crate) struct Cli {
+    /// ChilloutVR installation folder, overrides `chilloutFolder` from config.json
+    #[arg(long, global = true, value_name = "PATH")]
+    chillout_folder: Option<PathBuf>,
+
+    /// Maximum number of parallel downloads, overrides `maxConcurrentDownloads` from config.json
+    #[arg(long, short, global = true, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Use the catalog cached by the last successful fetch instead of the API
+    #[arg(long, global = true, conflicts_with = "catalog")]
+    offline: bool,
+
+    /// Load the mod catalog from a local JSON file instead of the API
+    #[arg(long, global = true, value_name = "FILE")]
+    catalog: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List every mod in the catalog
+    List {
+        /// Only list mods whose category contains this text
+        #[arg(long)]
+        category: Option<String>,
+    },
+    /// Search mods by name, alias, category or description
+    Search { query: String },
+    /// Show details about a mod
+    Info {
+        /// Mod id, name or alias
+        #[arg(value_name = "MOD")]
+        mod_name: String,
+    },
+    /// Download and install mods
+    Install {
+        /// Mod ids, names or aliases
+        #[arg(required = true, value_name = "MOD")]
+        mods: Vec<String>,
+
+        /// Do not install the mods' requirements
+        #[arg(long)]
+        no_deps: bool,
+    },
+    /// Remove installed mods
+    Uninstall {
+        /// Mod ids, names or aliases
+        #[arg(required = true, value_name = "MOD")]
+        mods: Vec<String>,
+
+        /// Also delete the mods' configuration files from the user data folder
+        #[arg(long)]
+        purge_config: bool,
+
+        /// Delete configuration files without asking first
+        #[arg(long, requires = "purge_config")]
+        yes: bool,
+
+        /// Remove mods even if other installed mods require them
+        #[arg(long)]
+        force: bool,
+    },
+    /// Find the mod that causes a problem by repeatedly disabling half of the enabled mods
+    Bisect {
+        /// Shell command that exits with 0 when the problem is gone, asks on the terminal
+        /// when not given
+        #[arg(long, value_name = "COMMAND")]
+        check: Option<String>,
+    },
+    /// Move mods into the Disabled folder so the game does not load them
+    Disable {
+        /// Mod ids, names or aliases
+        #[arg(value_name = "MOD", required_unless_present = "all_except_core")]
+        mods: Vec<String>,
+
+        /// Disable every installed mod except core libraries and their requirements
+        #[arg(long, conflicts_with = "mods")]
+        all_except_core: bool,
+    },
+    /// Move disabled mods back so the game loads them again
+    Enable {
+        /// Mod ids, names or aliases
+        #[arg(value_name = "MOD", required_unless_present = "all")]
+        mods: Vec<String>,
+
+        /// Enable every disabled mod
+        #[arg(long, conflicts_with = "mods")]
+        all: bool,
+    },
+    /// Update mods to their latest version, all installed mods when none are given
+    Update {
+        /// Mod ids, names or aliases
+        #[arg(value_name = "MOD")]
+        mods: Vec<String>,
+    },
+    /// Compare installed mods against the latest catalog versions
+    Outdated {
+        /// Also list mods that are up to date
+        #[arg(long)]
+        all: bool,
+    },
+    /// List installed mods that were marked broken or outdated since they were installed
+    Health {
+        /// Move broken and outdated mods into the Disabled folder
+        #[arg(long)]
+        disable: bool,
+    },
+    /// Identify the DLLs in Mods and Plugins by hash
+    Scan {
+        /// Record identified mods in the installed-mods manifest
+        #[arg(long)]
+        import: bool,
+    },
+    /// Find mods installed in Plugins and plugins installed in Mods
+    CheckPlacement {
+        /// Move misplaced files into the folder they belong in
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Install `MelonLoader` from a release archive
+    InstallLoader {
+        /// URL or path of the release zip, `melonLoaderUrl` from config.json when not given
+        #[arg(value_name = "ZIP")]
+        source: Option<String>,
+
+        /// Expected SHA-256 of the archive, in hex or base64. Required, also for the
+        /// default release, take it from the release page
+        #[arg(long, value_name = "HASH")]
+        sha256: String,
+    },
+    /// Replace the installed `MelonLoader` with another version, keeping mods and user data
+    SwitchLoader {
+        /// URL or path of the release zip, `melonLoaderUrl` from config.json when not given
+        #[arg(value_name = "ZIP")]
+        source: Option<String>,
+
+        /// Expected SHA-256 of the archive, in hex or base64. Required, also for the
+        /// default release, take it from the release page
+        #[arg(long, value_name = "HASH")]
+        sha256: String,
+
+        /// Switch even if installed mods do not support the new version
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show the state of the ChilloutVR installation
+    Status,
+}
+
+impl Cli {
+    fn chillout_folder(&self) -> Result<PathBuf, ApiError> {
+        if let Some(path) = &self.chillout_folder {
+            return Ok(path.clone());
+        }
+
+        let folder = config::CONFIGURATION_INSTANCE.chillout_folder();
+        if folder.is_empty() {
+            Err(ApiError::ChilloutFolderNotSet)
+        } else {
+            Ok(PathBuf::from(folder))
+        }
+    }
+
+    fn catalog_source(&self) -> CatalogSource {
+        match (&self.catalog, self.offline) {
+            (Some(path), _) => CatalogSource::File(path.clone()),
+            (None, true) => CatalogSource::Offline,
+            (None, false) => CatalogSource::Online,
+        }
+    }
+
+    async fn catalog(&self) -> Result<Vec<ModInfo>, ApiError> {
+        api::fetch_all_mods(&self.catalog_source()).await
+    }
+
+    /// The catalog for commands that still work without it, empty if it cannot be loaded.
+    async fn catalog_or_empty(&self) -> Vec<ModInfo> {
+        self.catalog().await.unwrap_or_else(|err| {
+            eprintln!("Mod catalog unavailable ({err}), continuing without it");
+            Vec::new()
+        })
+    }
+
+    fn max_concurrent_downloads(&self) -> usize {
+        self.jobs
+            .unwrap_or_else(|| config::CONFIGURATION_INSTANCE.max_concurrent_downloads())
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+pub(crate) async fn run(cli: Cli) -> Result<(), ApiError> {
+    match &cli.command {
+        Command::List { category } => {
+            list(&cli.catalog().await?, category.as_deref());
+            Ok(())
+        }
+        Command::Search { query } => search(&cli.catalog().await?, query),
+        Command::Info { mod_name } => info(&cli.catalog().await?, mod_name),
+        Command::Install { mods, no_deps } => {
+            let chillout_folder = cli.chillout_folder()?;
+            install(
+                &cli,
+                &cli.catalog().await?,
+                &chillout_folder,
+                mods,
+                *no_deps,
+            )
+            .await
+        }
+        Command::Uninstall {
+            mods,
+            purge_config,
+            yes,
+            force,
+        } => {
+            let chillout_folder = cli.chillout_folder()?;
+            let options = UninstallOptions {
+                purge_config: *purge_config,
+                yes: *yes,
+                force: *force,
+            };
+            uninstall(&cli.catalog().await?, &chillout_folder, mods, options).await
+        }
+        Command::Bisect { check } => {
+            let chillout_folder = cli.chillout_folder()?;
+            bisect(
+                &cli.catalog_or_empty().await,
+                &chillout_folder,
+                check.as_deref(),
+            )
+            .await
+        }
+        Command::Disable {
+            mods,
+            all_except_core,
+        } => {
+            let chillout_folder = cli.chillout_folder()?;
+            disable(
+                &cli.catalog().await?,
+                &chillout_folder,
+                mods,
+                *all_except_core,
+            )
+            .await
+        }
+        Command::Enable { mods, all } => {
+            let chillout_folder = cli.chillout_folder()?;
+            enable(&cli.catalog().await?, &chillout_folder, mods, *all).await
+        }
+        Command::Update { mods } => {
+            let chillout_folder = cli.chillout_folder()?;
+            update(&cli, &cli.catalog().await?, &chillout_folder, mods).await
+        }
+        Command::Outdated { all } => {
+            let chillout_folder = cli.chillout_folder()?;
+            outdated(&cli.catalog().await?, &chillout_folder, *all).await
+        }
+        Command::Health { disable } => {
+            let chillout_folder = cli.chillout_folder()?;
+            health(&cli.catalog().await?, &chillout_folder, *disable).await
+        }
+        Command::Scan { import } => {
+            let chillout_folder = cli.chillout_folder()?;
+            scan(&cli.catalog().await?, &chillout_folder, *import).await
+        }
+        Command::CheckPlacement { fix } => {
+            let chillout_folder = cli.chillout_folder()?;
+            check_placement(&cli.catalog_or_empty().await, &chillout_folder, *fix).await
+        }
+        Command::InstallLoader { source, sha256 } => {
+            install_loader(&cli.chillout_folder()?, source.as_deref(), sha256).await
+        }
+        Command::SwitchLoader {
+            source,
+            sha256,
+            force,
+        } => {
+            let chillout_folder = cli.chillout_folder()?;
+            switch_loader(
+                &cli.catalog().await?,
+                &chillout_folder,
+                source.as_deref(),
+                sha256,
+                *force,
+            )
+            .await
+        }
+        Command::Status => {
+            let chillout_folder = cli.chillout_folder()?;
+            status(&cli.catalog_or_empty().await, &chillout_folder).await
+        }
+    }
+}
+
+fn print_mod_line(mod_info: &ModInfo) {
+    let (version, status) = mod_info
+        .latest_version()
+        .map(|version| {
+            (
+                version.mod_version.to_string(),
+                version.approval_status.to_string(),
+            )
+        })
+        .unwrap_or_default();
+
+    println!(
+        "{:>4}  {:<32} {:<16} {}",
+        mod_info.id, mod_info.name, version, status
+    );
+}
+
+fn find_mods<'a>(mods: &'a [ModInfo], queries: &[String]) -> Result<Vec<&'a ModInfo>, ApiError> {
+    queries
+        .iter()
+        .map(|query| {
+            mod_info::find_mod(mods, query).ok_or_else(|| ApiError::ModNotFound(query.clone()))
+        })
+        .collect()
+}
+
+/// Versions of `mod_info` that have a matching DLL on disk, with the file they were found in.
+fn installed_versions<'a>(
+    mod_info: &'a ModInfo,
+    hashes: &'a HashMap<String, Vec<PathBuf>>,
+) -> Vec<(&'a ModVersion, &'a PathBuf)> {
+    mod_info
+        .versions
+        .iter()
+        .filter_map(|version| hashes.get(&version.hash).map(|paths| (version, paths)))
+        .flat_map(|(version, paths)| paths.iter().map(move |path| (version, path)))
+        .collect()
+}
+
+fn list(mods: &[ModInfo], category: Option<&str>) {
+    let category = category.map(str::to_lowercase);
+
+    for mod_info in mods {
+        let in_category = category.as_ref().is_none_or(|category| {
+            mod_info
+                .category
+                .as_ref()
+                .is_some_and(|c| c.to_lowercase().contains(category))
+        });
+
+        if in_category {
+            print_mod_line(mod_info);
+        }
+    }
+}
+
+fn search(mods: &[ModInfo], query: &str) -> Result<(), ApiError> {
+    let mut found = false;
+    for mod_info in mods
+        .iter()
+        .filter(|mod_info| mod_info.matches_search(query))
+    {
+        print_mod_line(mod_info);
+        found = true;
+    }
+
+    if found {
+        Ok(())
+    } else {
+        Err(ApiError::ModNotFound(query.to_string()))
+    }
+}
+
+fn info(mods: &[ModInfo], query: &str) -> Result<(), ApiError> {
+    let mod_info =
+        mod_info::find_mod(mods, query).ok_or_else(|| ApiError::ModNotFound(query.to_string()))?;
+    let version = mod_info
+        .latest_version()
+        .ok_or(ApiError::ModVersionNotFound)?;
+
+    println!("{} (id {})", mod_info.name, mod_info.id);
+    if let Some(aliases) = mod_info.aliases.as_ref().filter(|a| !a.is_empty()) {
+        println!("Aliases:        {}", aliases.join(", "));
+    }
+    if let Some(category) = &mod_info.category {
+        match categories::get_category_description(category) {
+            Some(description) => println!("Category:       {category} - {description}"),
+            None => println!("Category:       {category}"),
+        }
+    }
+    println!("Version:        {}", version.mod_version);
+    println!("Type:           {}", version.mod_type);
+    println!("Status:         {}", version.approval_status);
+    println!("Authors:        {}", version.get_authors_joined(", "));
+    println!("Game version:   {}", version.game_version);
+    println!("Loader version: {}", version.loader_version);
+    if !version.requirements.is_empty() {
+        let requirements: Vec<String> = version
+            .requirements
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        println!("Requirements:   {}", requirements.join(", "));
+    }
+    println!("Download:       {}", version.download_link);
+    println!("Source:         {}", version.source_link);
+    println!();
+    println!("{}", version.description);
+
+    Ok(())
+}
+
+/// Prints every outcome, then the manifest failure with the files it left unrecorded.
+fn report_outcomes(report: InstallReport, verb: &str) -> Result<(), ApiError> {
+    let mut first_error = FirstError::default();
+    let mut installed = Vec::new();
+
+    for outcome in report.outcomes {
+        match outcome.result {
+            Ok(path) => {
+                println!("{verb} {} to {}", outcome.mod_info.name, path.display());
+                installed.push(path);
+            }
+            Err(err) => first_error.record(&format!("install {}", outcome.mod_info.name), err),
+        }
+    }
+
+    if let Some(err) = report.manifest_error {
+        eprintln!();
+        first_error.record("record the installed mods in the manifest", err);
+        if !installed.is_empty() {
+            eprintln!("Installed but not recorded, `scan --import` picks them up again:");
+            for path in &installed {
+                eprintln!("  {}", path.display());
+            }
+        }
+    }
+
+    first_error.into_result()
+}
+
+async fn install(
+    cli: &Cli,
+    mods: &[ModInfo],
+    chillout_folder: &Path,
+    queries: &[String],
+    no_deps: bool,
+) -> Result<(), ApiError> {
+    let targets = find_mods(mods, queries)?;
+
+    let to_install = if no_deps {
+        targets
+    } else {
+        let installed: HashSet<usize> = outdated::collect_installed(chillout_folder, mods)
+            .await?
+            .iter()
+            .map(|installed| installed.id)
+            .collect();
+
+        // Requirements that are already present are left alone, explicitly requested mods are reinstalled
+        dependencies::resolve_install_order(mods, &targets)?
+            .into_iter()
+            .filter(|mod_info| {
+                targets.iter().any(|target| target.id == mod_info.id)
+                    || !installed.contains(&mod_info.id)
+            })
+            .collect()
+    };
+
+    let client = api::create_client()?;
+    let report = installer::install_mods(
+        &client,
+        &to_install,
+        chillout_folder,
+        cli.max_concurrent_downloads(),
+    )
+    .await;
+
+    report_outcomes(report, "Installed")
+}
+
+#[derive(Debug, Clone, Copy)]
+struct UninstallOptions {
+    purge_config: bool,
+    yes: bool,
+    force: bool,
+}
+
+async fn uninstall(
+    mods: &[ModInfo],
+    chillout_folder: &Path,
+    queries: &[String],
+    options: UninstallOptions,
+) -> Result<(), ApiError> {
+    let installed = outdated::collect_installed(chillout_folder, mods).await?;
+    let hashes = utils::hash_installed_dlls(chillout_folder).await?;
+
+    // Every target is planned before anything is deleted, so a refusal leaves all of them
+    // intact and mods removed in the same command do not count as dependents
+    let mut plans: Vec<uninstaller::UninstallPlan> = Vec::new();
+    for query in queries {
+        let plan = uninstaller::plan_uninstall(
+            mods,
+            &installed,
+            &hashes,
+            query,
+            chillout_folder,
+            options.purge_config,
+        )
+        .await?;
+        if plans
+            .iter()
+            .all(|planned| planned.target.id != plan.target.id)
+        {
+            plans.push(plan);
+        }
+    }
+
+    if let Err(err) = uninstaller::check_dependents(&mut plans, options.force) {
+        eprintln!("Run again with --force to remove it anyway.");
+        return Err(err);
+    }
+    for plan in plans.iter().filter(|plan| !plan.dependents.is_empty()) {
+        eprintln!(
+            "Warning: {} is still required by {}",
+            plan.target.name,
+            plan.dependents.join(", ")
+        );
+    }
+
+    for mut plan in plans {
+        let name = plan.target.name.clone();
+
+        if !plan.config.is_empty() && !options.yes {
+            println!("Configuration of {name} that will be deleted:");
+            for path in &plan.config {
+                println!("  {}", path.display());
+            }
+
+            if !utils::confirm("Delete these from UserData?", Some(false)).await? {
+                println!("Keeping the configuration of {name}");
+                plan.config.clear();
+            }
+        }
+
+        let report = uninstaller::uninstall_mod(chillout_folder, plan).await?;
+        for path in report.removed_files.iter().chain(&report.removed_config) {
+            println!("Removed {} ({})", report.name, path.display());
+        }
+    }
+
+    Ok(())
+}
+
+async fn update(
+    cli: &Cli,
+    mods: &[ModInfo],
+    chillout_folder: &Path,
+    queries: &[String],
+) -> Result<(), ApiError> {
+    let hashes = utils::hash_installed_dlls(chillout_folder).await?;
+
+    let targets = if queries.is_empty() {
+        mods.iter()
+            .filter(|mod_info| !installed_versions(mod_info, &hashes).is_empty())
+            .collect()
+    } else {
+        find_mods(mods, queries)?
+    };
+
+    let mut to_update = Vec::new();
+    let mut old_paths: HashMap<usize, Vec<PathBuf>> = HashMap::new();
+
+    for mod_info in targets {
+        let latest = mod_info
+            .latest_version()
+            .ok_or(ApiError::ModVersionNotFound)?;
+        let installed = installed_versions(mod_info, &hashes);
+
+        if installed.is_empty() {
+            return Err(ApiError::ModNotInstalled(mod_info.name.clone()));
+        }
+
+        if installed
+            .iter()
+            .any(|(version, _)| version.hash == latest.hash)
+        {
+            println!("{} is up to date ({})", mod_info.name, latest.mod_version);
+            continue;
+        }
+
+        to_update.push(mod_info);
+        old_paths.insert(
+            mod_info.id,
+            installed
+                .into_iter()
+                .map(|(_, path)| path.clone())
+                .collect(),
+        );
+    }
+
+    let client = api::create_client()?;
+    let mut report = installer::install_mods(
+        &client,
+        &to_update,
+        chillout_folder,
+        cli.max_concurrent_downloads(),
+    )
+    .await;
+
+    let mut first_error = FirstError::default();
+    for outcome in &mut report.outcomes {
+        let Ok(new_path) = &mut outcome.result else {
+            continue;
+        };
+
+        let old = old_paths.get(&outcome.mod_info.id).into_iter().flatten();
+        let mut was_disabled = false;
+        for old_path in old {
+            was_disabled |= disabler::is_disabled_path(old_path);
+            if old_path != &*new_path {
+                if let Err(err) = tokio::fs::remove_file(old_path).await {
+                    first_error.record(&format!("remove {}", old_path.display()), err.into());
+                }
+            }
+        }
+
+        // Updates land in Mods/Plugins, put disabled mods back aside
+        if was_disabled {
+            match disable_again(chillout_folder, outcome.mod_info.id).await {
+                Ok(Some(path)) => *new_path = path,
+                Ok(None) => {}
+                Err(err) => {
+                    first_error.record(&format!("disable {} again", outcome.mod_info.name), err);
+                }
+            }
+        }
+    }
+
+    // Cleanup failures are printed first, so they also come first in the result
+    let reported = report_outcomes(report, "Updated");
+    first_error.into_result().and(reported)
+}
+
+/// Disables the freshly recorded version of mod `id`, `None` if the manifest does not list it.
+async fn disable_again(chillout_folder: &Path, id: usize) -> Result<Option<PathBuf>, ApiError> {
+    let manifest = Manifest::load(chillout_folder).await?;
+    match manifest.get(id) {
+        Some(installed) => Ok(Some(
+            disabler::disable_mod(chillout_folder, installed).await?,
+        )),
+        None => Ok(None),
+    }
+}
+
+async fn bisect(
+    mods: &[ModInfo],
+    chillout_folder: &Path,
+    check: Option<&str>,
+) -> Result<(), ApiError> {
+    let check = check.map_or(bisect::Check::Prompt, |command| {
+        bisect::Check::Command(command.to_string())
+    });
+
+    let outcome = bisect::bisect(chillout_folder, mods, &check).await?;
+    println!();
+    match outcome {
+        BisectOutcome::Found(Candidate::Installed(culprit)) => println!(
+            "{} {} causes the problem, run `disable {}` to keep it off",
+            culprit.name, culprit.mod_version, culprit.id
+        ),
+        BisectOutcome::Found(Candidate::Unidentified { file, location }) => println!(
+            "{} causes the problem, move it into {}/{} to keep it off",
+            file.display(),
+            location.folder_name(),
+            disabler::DISABLED_FOLDER_NAME
+        ),
+        BisectOutcome::NotReproducible => {
+            println!("The problem does not happen with every mod enabled");
+        }
+        BisectOutcome::NotCausedByMods => {
+            println!(
+                "The problem happens with every mod and DLL disabled, it is not caused by a mod"
+            );
+        }
+        BisectOutcome::NothingEnabled => println!("No enabled mods to bisect"),
+    }
+    println!("Every mod that was enabled before is enabled again");
+
+    Ok(())
+}
+
+async fn disable(
+    mods: &[ModInfo],
+    chillout_folder: &Path,
+    queries: &[String],
+    all_except_core: bool,
+) -> Result<(), ApiError> {
+    let installed = outdated::collect_installed(chillout_folder, mods).await?;
+
+    let targets = if all_except_core {
+        disabler::all_except_core(mods, &installed)
+    } else {
+        find_installed_mods(mods, &installed, queries)?
+    };
+
+    let mut first_error = FirstError::default();
+    for target in targets {
+        if target.is_disabled() {
+            println!("{} is already disabled", target.name);
+            continue;
+        }
+
+        match disabler::disable_mod(chillout_folder, target).await {
+            Ok(path) => println!("Disabled {}, moved to {}", target.name, path.display()),
+            Err(err) => first_error.record(&format!("disable {}", target.name), err),
+        }
+    }
+
+    first_error.into_result()
+}
+
+async fn enable(
+    mods: &[ModInfo],
+    chillout_folder: &Path,
+    queries: &[String],
+    all: bool,
+) -> Result<(), ApiError> {
+    let installed = outdated::collect_installed(chillout_folder, mods).await?;
+
+    let targets = if all {
+        installed
+            .iter()
+            .filter(|installed| installed.is_disabled())
+            .collect()
+    } else {
+        find_installed_mods(mods, &installed, queries)?
+    };
+
+    let mut first_error = FirstError::default();
+    for target in targets {
+        if !target.is_disabled() {
+            println!("{} is already enabled", target.name);
+            continue;
+        }
+
+        match disabler::enable_mod(chillout_folder, target).await {
+            Ok(path) => println!("Enabled {}, moved to {}", target.name, path.display()),
+            Err(err) => first_error.record(&format!("enable {}", target.name), err),
+        }
+    }
+
+    first_error.into_result()
+}
+
+fn find_installed_mods<'a>(
+    mods: &[ModInfo],
+    installed: &'a [InstalledMod],
+    queries: &[String],
+) -> Result<Vec<&'a InstalledMod>, ApiError> {
+    queries
+        .iter()
+        .map(|query| {
+            manifest::find_installed(mods, installed, query)
+                .ok_or_else(|| ApiError::ModNotInstalled(query.clone()))
+        })
+        .collect()
+}
+
+async fn outdated(mods: &[ModInfo], chillout_folder: &Path, all: bool) -> Result<(), ApiError> {
+    let reports = outdated::check_outdated(chillout_folder, mods).await?;
+
+    for report in reports
+        .iter()
+        .filter(|report| all || report.state != OutdatedState::UpToDate)
+    {
+        let available = report
+            .available_version
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        let status = report
+            .approval_status
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+
+        println!(
+            "{:<32} {:<16} -> {:<16} {:<32} {}",
+            report.installed.name,
+            report.installed.mod_version,
+            available,
+            report.describe(),
+            status
+        );
+    }
+
+    Ok(())
+}
+
+async fn health(mods: &[ModInfo], chillout_folder: &Path, disable: bool) -> Result<(), ApiError> {
+    let changes = health::check_health(chillout_folder, mods).await?;
+    if changes.is_empty() {
+        println!("No installed mod changed its approval status");
+        return Ok(());
+    }
+
+    for change in &changes {
+        let previous = change
+            .previous
+            .as_ref()
+            .map_or_else(|| "unknown".to_string(), ToString::to_string);
+        let disabled = if change.installed.is_disabled() {
+            " [disabled]"
+        } else {
+            ""
+        };
+
+        println!(
+            "{} {}: {previous} -> {}{disabled}",
+            change.installed.name,
+            change.installed.mod_version,
+            change.describe_current()
+        );
+    }
+
+    let to_disable: Vec<_> = changes
+        .iter()
+        .filter(|change| change.needs_attention() && !change.installed.is_disabled())
+        .collect();
+    if to_disable.is_empty() {
+        return Ok(());
+    }
+
+    if !disable {
+        println!();
+        println!("Run again with --disable to move broken and outdated mods aside");
+        return Ok(());
+    }
+
+    println!();
+    let mut first_error = FirstError::default();
+    for change in to_disable {
+        match disabler::disable_mod(chillout_folder, &change.installed).await {
+            Ok(path) => println!(
+                "Disabled {}, moved to {}",
+                change.installed.name,
+                path.display()
+            ),
+            Err(err) => first_error.record(&format!("disable {}", change.installed.name), err),
+        }
+    }
+
+    first_error.into_result()
+}
+
+async fn scan(mods: &[ModInfo], chillout_folder: &Path, import: bool) -> Result<(), ApiError> {
+    let scanned = scanner::scan_mod_folders(chillout_folder, mods).await?;
+
+    for file in &scanned {
+        let name = scanner::scanned_version(mods, file).map_or_else(
+            || describe_unknown(mods, file),
+            |(mod_info, version)| format!("{} {}", mod_info.name, version.mod_version),
+        );
+        let relative = file
+            .path
+            .strip_prefix(chillout_folder)
+            .unwrap_or(&file.path);
+
+        println!("{:<48} {:<12} {}", relative.display(), file.class, name);
+    }
+
+    if import {
+        let mut manifest = Manifest::load(chillout_folder).await?;
+        let imported =
+            scanner::import_into_manifest(&mut manifest, &scanned, mods, chillout_folder);
+        manifest.save(chillout_folder).await?;
+
+        println!();
+        println!("Imported {} mod(s) into the manifest", imported.len());
+    }
+
+    Ok(())
+}
+
+/// Name and version from the `MelonInfo` of an unidentified file, and the catalog mod it
+/// most likely is.
+fn describe_unknown(mods: &[ModInfo], file: &scanner::ScannedFile) -> String {
+    let Some(assembly) = &file.assembly else {
+        return String::new();
+    };
+
+    let description = match (&assembly.melon_info, &assembly.assembly_name) {
+        (Some(info), _) => match &info.author {
+            Some(author) => format!("{} {} by {author}", info.name, info.version),
+            None => format!("{} {}", info.name, info.version),
+        },
+        (None, Some(name)) => format!("{name} (not a MelonLoader mod)"),
+        (None, None) => String::new(),
+    };
+
+    let guess = file.guessed.as_ref().and_then(|(id, version)| {
+        let mod_info = mods.iter().find(|mod_info| mod_info.id == *id)?;
+        Some(match version {
+            Some(version) => format!("{} {version}", mod_info.name),
+            None => mod_info.name.clone(),
+        })
+    });
+
+    match guess {
+        Some(guess) => format!("{description}, looks like {guess}"),
+        None => description,
+    }
+}
+
+async fn check_placement(
+    mods: &[ModInfo],
+    chillout_folder: &Path,
+    fix: bool,
+) -> Result<(), ApiError> {
+    let misplaced = placement::find_misplaced(chillout_folder, mods).await?;
+    if misplaced.is_empty() {
+        println!("Every mod and plugin is in the right folder");
+        return Ok(());
+    }
+
+    for file in &misplaced {
+        let relative = file
+            .path
+            .strip_prefix(chillout_folder)
+            .unwrap_or(&file.path);
+        println!(
+            "{:<48} {} is a {}, it belongs in {}",
+            relative.display(),
+            file.name,
+            file.expected,
+            file.expected.folder_name()
+        );
+    }
+
+    if !fix {
+        println!();
+        println!("Run again with --fix to move them");
+        return Ok(());
+    }
+
+    println!();
+    let report = placement::move_misplaced(chillout_folder, &misplaced).await?;
+    let mut first_error = FirstError::default();
+    let mut moved = Vec::new();
+    for (file, result) in report.outcomes {
+        match result {
+            Ok(destination) => {
+                println!("Moved {} to {}", file.name, destination.display());
+                moved.push(destination);
+            }
+            Err(err) => first_error.record(&format!("move {}", file.name), err),
+        }
+    }
+
+    if let Some(err) = report.manifest_error {
+        eprintln!();
+        first_error.record("record the new locations in the manifest", err);
+        if !moved.is_empty() {
+            eprintln!("Moved, but the manifest still lists their old locations:");
+            for path in &moved {
+                eprintln!("  {}", path.display());
+            }
+        }
+    }
+
+    first_error.into_result()
+}
+
+/// The archive to install the loader from, the configured release without a source.
+fn loader_archive(source: Option<&str>) -> LoaderArchive {
+    let config = &config::CONFIGURATION_INSTANCE;
+    LoaderArchive::from_source(source.unwrap_or_else(|| config.melon_loader_url()))
+}
+
+async fn install_loader(
+    chillout_folder: &Path,
+    source: Option<&str>,
+    sha256: &str,
+) -> Result<(), ApiError> {
+    let archive = loader_archive(source);
+    let client = api::create_client()?;
+
+    println!("Installing MelonLoader from {archive}");
+    for path in loader_installer::install_loader(&client, chillout_folder, &archive, sha256).await?
+    {
+        println!("Added {}", path.display());
+    }
+
+    print_loader_status(chillout_folder).await
+}
+
+async fn switch_loader(
+    mods: &[ModInfo],
+    chillout_folder: &Path,
+    source: Option<&str>,
+    sha256: &str,
+    force: bool,
+) -> Result<(), ApiError> {
+    let archive = loader_archive(source);
+    let client = api::create_client()?;
+
+    println!("Preparing MelonLoader from {archive}");
+    let staged = loader_installer::stage_loader(&client, chillout_folder, &archive, sha256).await?;
+
+    if let Err(err) = check_staged_loader(mods, chillout_folder, &archive, &staged, force).await {
+        staged.discard().await;
+        return Err(err);
+    }
+
+    let outcome = loader_installer::switch_loader(chillout_folder, staged).await?;
+    println!();
+    for path in &outcome.installed {
+        println!("Installed {}", path.display());
+    }
+    if let Some(backup) = &outcome.backup {
+        println!(
+            "Previous MelonLoader settings and logs saved to {}",
+            backup.display()
+        );
+    }
+
+    print_loader_status(chillout_folder).await
+}
+
+/// Checks the installed mods against the staged loader, refusing unless `force` if any of
+/// them does not support it or its version cannot be told.
+async fn check_staged_loader(
+    mods: &[ModInfo],
+    chillout_folder: &Path,
+    archive: &LoaderArchive,
+    staged: &StagedLoader,
+    force: bool,
+) -> Result<(), ApiError> {
+    let Some(version) = staged.version().await? else {
+        if force {
+            println!("Could not tell the new MelonLoader version, mods were not checked");
+            return Ok(());
+        }
+        println!("Run again with --force to switch without checking mods.");
+        return Err(ApiError::UnknownLoaderVersion(archive.to_string()));
+    };
+    println!("New MelonLoader version: {version}");
+
+    let installed = outdated::collect_installed(chillout_folder, mods).await?;
+    let incompatible = loader::incompatible_mods(mods, &installed, &version);
+    let Some((first, required)) = incompatible.first() else {
+        return Ok(());
+    };
+
+    println!();
+    println!("Incompatible with the new version:");
+    for (installed, requirement) in &incompatible {
+        println!(
+            "  {} {} requires MelonLoader {requirement}",
+            installed.name, installed.mod_version
+        );
+    }
+    if force {
+        return Ok(());
+    }
+
+    println!();
+    println!("Run again with --force to switch anyway.");
+    Err(ApiError::IncompatibleLoader {
+        mod_name: first.name.clone(),
+        required: required.to_string(),
+        installed: version.to_string(),
+    })
+}
+
+async fn print_loader_status(chillout_folder: &Path) -> Result<(), ApiError> {
+    let Some(install) = loader::detect_loader(chillout_folder).await? else {
+        println!("MelonLoader:       not installed");
+        return Ok(());
+    };
+
+    let version = install.version.as_ref().map_or_else(
+        || "unknown version".to_string(),
+        |(version, _)| version.to_string(),
+    );
+    let runtime = install
+        .runtime
+        .map_or_else(String::new, |runtime| format!(" {runtime}"));
+    let state = if install.is_broken() { ", broken" } else { "" };
+    println!("MelonLoader:       {version}{runtime}{state}");
+
+    if let Some((_, source)) = &install.version {
+        println!("                   version from {source}");
+    }
+    for path in &install.missing {
+        println!("                   missing {}", path.display());
+    }
+    for path in &install.corrupt {
+        println!("                   unreadable {}", path.display());
+    }
+
+    Ok(())
+}
+
+async fn status(mods: &[ModInfo], chillout_folder: &Path) -> Result<(), ApiError> {
+    println!("ChilloutVR folder: {}", chillout_folder.display());
+    print_loader_status(chillout_folder).await?;
+
+    let game_version = game::detect_game_version(chillout_folder).await?;
+    match &game_version {
+        Some(detected) => println!(
+            "Game version:      {} (from {})",
+            detected.version, detected.source
+        ),
+        None => println!("Game version:      unknown"),
+    }
+
+    let hashes = utils::hash_installed_dlls(chillout_folder).await?;
+    let mut known_files = HashSet::new();
+
+    println!();
+    for mod_info in mods {
+        for (version, path) in installed_versions(mod_info, &hashes) {
+            // A file can match several catalog versions, count it once
+            known_files.insert(path);
+            let latest = mod_info
+                .latest_version()
+                .map(|latest| latest.mod_version.to_string())
+                .unwrap_or_default();
+
+            let made_for = game_version
+                .as_ref()
+                .filter(|detected| !detected.supports(&version.game_version))
+                .map(|_| format!(", made for {}", version.game_version))
+                .unwrap_or_default();
+
+            println!(
+                "{:<32} {:<16} latest {:<16} {}{made_for}",
+                mod_info.name,
+                version.mod_version,
+                latest,
+                path.display()
+            );
+        }
+    }
+
+    let total_files: usize = hashes.values().map(Vec::len).sum();
+    let manifest = Manifest::load(chillout_folder).await?;
+    println!();
+    println!(
+        "{} known, {} unknown DLL(s), {} tracked in {}",
+        known_files.len(),
+        total_files - known_files.len(),
+        manifest.installed().len(),
+        manifest::manifest_path(chillout_folder).display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, Command};
+    use crate::api::CatalogSource;
+    use clap::{error::ErrorKind, Parser};
+    use std::path::PathBuf;
+
+    type ParseCase = (&'static [&'static str], fn(&Command) -> bool);
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("CVRModManager").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn parses_subcommands() {
+        // (arguments, expected command)
+        let cases: [ParseCase; 12] = [
+            (
+                &["list", "--category", "Utility"],
+                |command| matches!(command, Command::List { category: Some(category) } if category == "Utility"),
+            ),
+            (
+                &["info", "113"],
+                |command| matches!(command, Command::Info { mod_name } if mod_name == "113"),
+            ),
+            (
+                &["install", "BTKUILib", "Blackout", "--no-deps"],
+                |command| matches!(command, Command::Install { mods, no_deps: true } if mods == &["BTKUILib", "Blackout"]),
+            ),
+            (
+                &["uninstall", "Blackout", "--purge-config", "--yes"],
+                |command| {
+                    matches!(
+                        command,
+                        Command::Uninstall {
+                            purge_config: true,
+                            yes: true,
+                            force: false,
+                            ..
+                        }
+                    )
+                },
+            ),
+            (
+                &["bisect", "--check", "exit 0"],
+                |command| matches!(command, Command::Bisect { check: Some(check) } if check == "exit 0"),
+            ),
+            (
+                &["disable", "--all-except-core"],
+                |command| matches!(command, Command::Disable { mods, all_except_core: true } if mods.is_empty()),
+            ),
+            (
+                &["enable", "Blackout"],
+                |command| matches!(command, Command::Enable { mods, all: false } if mods == &["Blackout"]),
+            ),
+            (
+                &["update"],
+                |command| matches!(command, Command::Update { mods } if mods.is_empty()),
+            ),
+            (&["health", "--disable"], |command| {
+                matches!(command, Command::Health { disable: true })
+            }),
+            (&["scan", "--import"], |command| {
+                matches!(command, Command::Scan { import: true })
+            }),
+            (
+                &[
+                    "switch-loader",
+                    "MelonLoader.x64.zip",
+                    "--sha256",
+                    "abc",
+                    "--force",
+                ],
+                |command| {
+                    matches!(
+                        command,
+                        Command::SwitchLoader { source: Some(source), force: true, .. }
+                            if source == "MelonLoader.x64.zip"
+                    )
+                },
+            ),
+            (&["status"], |command| matches!(command, Command::Status)),
+        ];
+
+        for (args, expected) in cases {
+            let cli = parse(args).unwrap_or_else(|err| panic!("{args:?}: {err}"));
+            assert!(expected(&cli.command), "{args:?}: {:?}", cli.command);
+        }
+    }
+
+    #[test]
+    fn global_flags_go_anywhere() {
+        let cli = parse(&[
+            "outdated",
+            "--offline",
+            "-j",
+            "8",
+            "--chillout-folder",
+            "Game",
+        ])
+        .unwrap();
+        assert_eq!(cli.catalog_source(), CatalogSource::Offline);
+        assert_eq!(cli.jobs, Some(8));
+        assert_eq!(cli.chillout_folder, Some(PathBuf::from("Game")));
+
+        let cli = parse(&["--catalog", "mods.json", "list"]).unwrap();
+        assert_eq!(
+            cli.catalog_source(),
+            CatalogSource::File(PathBuf::from("mods.json"))
+        );
+        assert_eq!(
+            parse(&["list"]).unwrap().catalog_source(),
+            CatalogSource::Online
+        );
+    }
+
+    #[test]
+    fn rejects_conflicting_and_missing_arguments() {
+        // (arguments, error)
+        let cases: [(&[&str], ErrorKind); 9] = [
+            (&[], ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand),
+            (&["install"], ErrorKind::MissingRequiredArgument),
+            (&["uninstall"], ErrorKind::MissingRequiredArgument),
+            (
+                &["uninstall", "Blackout", "--yes"],
+                ErrorKind::MissingRequiredArgument,
+            ),
+            (&["disable"], ErrorKind::MissingRequiredArgument),
+            (
+                &["disable", "Blackout", "--all-except-core"],
+                ErrorKind::ArgumentConflict,
+            ),
+            (
+                &["enable", "Blackout", "--all"],
+                ErrorKind::ArgumentConflict,
+            ),
+            (
+                &["list", "--offline", "--catalog", "mods.json"],
+                ErrorKind::ArgumentConflict,
+            ),
+            (&["install-loader"], ErrorKind::MissingRequiredArgument),
+        ];
+
+        for (args, kind) in cases {
+            match parse(args) {
+                Ok(cli) => panic!("{args:?} parsed as {:?}", cli.command),
+                Err(err) => assert_eq!(err.kind(), kind, "{args:?}: {err}"),
+            }
+        }
+    }
+}
@@ -1,7 +1,9 @@
-use std::{path::Path, sync::LazyLock};
+use std::{path::Path, sync::LazyLock, time::Duration};
 
 use serde::{Deserialize, Serialize};
 
+use crate::api::mod_version::ApprovalStatus;
+
 const CONFIG_FILE_NAME: &str = "config.json";
 
 pub static CONFIGURATION_INSTANCE: LazyLock<CVRMelonConfig> = LazyLock::new(|| {
@@ -24,11 +26,100 @@ pub static CONFIGURATION_INSTANCE: LazyLock<CVRMelonConfig> = LazyLock::new(|| {
     }
 });
 
-#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+/// The official CVR Modding Group API.
+pub const DEFAULT_API_BASE_URL: &str = "https://api.cvrmg.com";
+/// Environment variable that overrides `apiBaseUrl`, handy for pointing at a staging instance.
+pub const API_BASE_URL_ENV: &str = "CVRMM_API_URL";
+
+/// `MelonLoader` release for 64-bit games installed when no archive is given. Pinned to a
+/// version, since the archive behind a `latest` link changes and with it the `--sha256` to pass.
+pub const DEFAULT_MELON_LOADER_URL: &str =
+    "https://github.com/LavaGang/MelonLoader/releases/download/v0.6.1/MelonLoader.x64.zip";
+
+const DEFAULT_MAX_CONCURRENT_DOWNLOADS: usize = 4;
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// Which approval states a mod may be in to be installed or updated.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallPolicy {
+    /// Only mods reviewed and approved by the modding group.
+    ApprovedOnly,
+    /// Approved mods, and mods marked outdated that may still work.
+    #[default]
+    AllowOutdated,
+    /// Everything, including broken mods and mods awaiting approval.
+    AllowAll,
+}
+
+impl InstallPolicy {
+    pub(crate) fn allows(self, status: &ApprovalStatus) -> bool {
+        match self {
+            Self::ApprovedOnly => matches!(status, ApprovalStatus::Approved),
+            Self::AllowOutdated => {
+                matches!(
+                    status,
+                    ApprovalStatus::Approved | ApprovalStatus::Outdated(_)
+                )
+            }
+            Self::AllowAll => true,
+        }
+    }
+}
+
+impl std::fmt::Display for InstallPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ApprovedOnly => write!(f, "approvedOnly"),
+            Self::AllowOutdated => write!(f, "allowOutdated"),
+            Self::AllowAll => write!(f, "allowAll"),
+        }
+    }
+}
+
+/// What happens when a mod targets another `MelonLoader` version than the installed one.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum LoaderCheck {
+    /// Install without comparing versions.
+    Ignore,
+    /// Install anyway and print a warning.
+    #[default]
+    Warn,
+    /// Refuse to install the mod.
+    Refuse,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(default, rename_all = "camelCase")]
 #[allow(clippy::module_name_repetitions)]
 pub struct CVRMelonConfig {
     chillout_folder: String,
+    api_base_url: String,
+    api_mirrors: Vec<String>,
+    max_concurrent_downloads: usize,
+    max_retries: u32,
+    retry_base_delay_ms: u64,
+    install_policy: InstallPolicy,
+    loader_check: LoaderCheck,
+    melon_loader_url: String,
+}
+
+impl Default for CVRMelonConfig {
+    fn default() -> Self {
+        Self {
+            chillout_folder: String::new(),
+            api_base_url: DEFAULT_API_BASE_URL.to_string(),
+            api_mirrors: Vec::new(),
+            max_concurrent_downloads: DEFAULT_MAX_CONCURRENT_DOWNLOADS,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_base_delay_ms: DEFAULT_RETRY_BASE_DELAY_MS,
+            install_policy: InstallPolicy::default(),
+            loader_check: LoaderCheck::default(),
+            melon_loader_url: DEFAULT_MELON_LOADER_URL.to_string(),
+        }
+    }
 }
 
 impl CVRMelonConfig {
@@ -51,6 +142,70 @@ impl CVRMelonConfig {
         &self.chillout_folder
     }
 
+    /// Base URL of the mod API, `CVRMM_API_URL` takes precedence over the config file.
+    #[must_use]
+    pub fn api_base_url(&self) -> String {
+        self.api_base_url_with(std::env::var(API_BASE_URL_ENV).ok())
+    }
+
+    /// `api_base_url` with `override_url` in place of the environment variable.
+    fn api_base_url_with(&self, override_url: Option<String>) -> String {
+        override_url
+            .filter(|url| !url.trim().is_empty())
+            .unwrap_or_else(|| self.api_base_url.clone())
+    }
+
+    /// Mirrors of the mod API, tried in order when the base URL fails.
+    #[must_use]
+    pub fn api_mirrors(&self) -> &[String] {
+        &self.api_mirrors
+    }
+
+    /// Checks that the base URL and every mirror are `http://` or `https://` URLs.
+    ///
+    /// # Errors
+    ///
+    /// Names the first one that is not.
+    pub fn check_api_urls(&self) -> Result<(), String> {
+        check_api_urls(&self.api_base_url(), &self.api_mirrors)
+    }
+
+    /// How many mods are downloaded at the same time, never less than one.
+    #[must_use]
+    pub fn max_concurrent_downloads(&self) -> usize {
+        self.max_concurrent_downloads.max(1)
+    }
+
+    /// How often a failed request is retried after the first attempt.
+    #[must_use]
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Delay before the first retry, doubled for every following one.
+    #[must_use]
+    pub fn retry_base_delay(&self) -> Duration {
+        Duration::from_millis(self.retry_base_delay_ms)
+    }
+
+    /// Approval states a mod may be in to be installed or updated.
+    #[must_use]
+    pub fn install_policy(&self) -> InstallPolicy {
+        self.install_policy
+    }
+
+    /// What to do with mods made for another `MelonLoader` version.
+    #[must_use]
+    pub fn loader_check(&self) -> LoaderCheck {
+        self.loader_check
+    }
+
+    /// Where `MelonLoader` release archives are downloaded from when no source is given.
+    #[must_use]
+    pub fn melon_loader_url(&self) -> &str {
+        &self.melon_loader_url
+    }
+
     /// Sets the chillout folder path.
     ///
     /// # Errors
@@ -71,3 +226,116 @@ impl CVRMelonConfig {
         }
     }
 }
+
+fn check_api_urls(base_url: &str, mirrors: &[String]) -> Result<(), String> {
+    let urls = std::iter::once(("apiBaseUrl", base_url))
+        .chain(mirrors.iter().map(|mirror| ("apiMirrors", mirror.as_str())));
+
+    for (setting, url) in urls {
+        let host = url
+            .trim()
+            .strip_prefix("https://")
+            .or_else(|| url.trim().strip_prefix("http://"));
+        if host.is_none_or(|host| host.trim_matches('/').is_empty()) {
+            return Err(format!("{setting} entry \"{url}\" is not an http(s) URL"));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_api_urls, CVRMelonConfig, InstallPolicy, DEFAULT_API_BASE_URL};
+    use crate::api::mod_version::ApprovalStatus;
+
+    #[test]
+    fn environment_overrides_the_base_url() {
+        let config = CVRMelonConfig {
+            api_base_url: "https://mirror.example.com".to_string(),
+            ..CVRMelonConfig::default()
+        };
+
+        // (environment variable, base URL)
+        let cases: [(Option<&str>, &str); 4] = [
+            (None, "https://mirror.example.com"),
+            (Some(""), "https://mirror.example.com"),
+            (Some("  "), "https://mirror.example.com"),
+            (Some("http://127.0.0.1:8765"), "http://127.0.0.1:8765"),
+        ];
+
+        for (env, base_url) in cases {
+            assert_eq!(
+                config.api_base_url_with(env.map(str::to_string)),
+                base_url,
+                "{env:?}"
+            );
+        }
+        assert_eq!(
+            CVRMelonConfig::default().api_base_url_with(None),
+            DEFAULT_API_BASE_URL
+        );
+    }
+
+    #[test]
+    fn policies_allow_approval_states() {
+        let statuses = [
+            ApprovalStatus::Approved,
+            ApprovalStatus::AwaitingApproval,
+            ApprovalStatus::Outdated(None),
+            ApprovalStatus::Outdated(Some("Broke with the 2025r180 update".to_string())),
+            ApprovalStatus::Broken(None),
+            ApprovalStatus::Broken(Some("Crashes on load".to_string())),
+        ];
+
+        // (policy, allowed for each of `statuses`)
+        let cases: [(InstallPolicy, [bool; 6]); 3] = [
+            (
+                InstallPolicy::ApprovedOnly,
+                [true, false, false, false, false, false],
+            ),
+            (
+                InstallPolicy::AllowOutdated,
+                [true, false, true, true, false, false],
+            ),
+            (InstallPolicy::AllowAll, [true; 6]),
+        ];
+
+        for (policy, allowed) in cases {
+            for (status, allowed) in statuses.iter().zip(allowed) {
+                assert_eq!(policy.allows(status), allowed, "{policy} {status}");
+            }
+        }
+        assert_eq!(InstallPolicy::default(), InstallPolicy::AllowOutdated);
+    }
+
+    #[test]
+    fn checks_api_urls() {
+        // (base URL, mirrors, setting in the error)
+        let cases: [(&str, &[&str], Option<&str>); 7] = [
+            (DEFAULT_API_BASE_URL, &[], None),
+            (
+                DEFAULT_API_BASE_URL,
+                &["https://mirror.example.com/", "http://127.0.0.1:8765"],
+                None,
+            ),
+            ("", &[], Some("apiBaseUrl")),
+            ("api.cvrmg.com", &[], Some("apiBaseUrl")),
+            (DEFAULT_API_BASE_URL, &[""], Some("apiMirrors")),
+            (DEFAULT_API_BASE_URL, &["  "], Some("apiMirrors")),
+            (DEFAULT_API_BASE_URL, &["https://"], Some("apiMirrors")),
+        ];
+
+        for (base_url, mirrors, setting) in cases {
+            let mirrors: Vec<String> = mirrors.iter().map(ToString::to_string).collect();
+            let result = check_api_urls(base_url, &mirrors);
+            match setting {
+                None => assert_eq!(result, Ok(()), "{base_url} {mirrors:?}"),
+                Some(setting) => {
+                    let err = result.unwrap_err();
+                    assert!(err.starts_with(setting), "{base_url} {mirrors:?}: {err}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use crate::api::{api_error::ApiError, mod_info::ModInfo, requirement::Requirement};
+use crate::manifest::InstalledMod;
+use std::collections::HashMap;
+
+/// Finds the catalog entry a requirement points at, by the mod id in its
+/// `/mods/download/<id>` link first and by name or alias otherwise.
+pub(crate) fn find_requirement<'a>(
+    mods: &'a [ModInfo],
+    requirement: &Requirement,
+) -> Option<&'a ModInfo> {
+    requirement
+        .mod_id
+        .and_then(|id| mods.iter().find(|mod_info| mod_info.id == id))
+        .or_else(|| {
+            mods.iter()
+                .find(|mod_info| mod_info.matches_name(&requirement.name))
+        })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Visiting,
+    Done,
+}
+
+/// Returns `targets` together with all of their transitive requirements,
+/// ordered so every mod comes after the mods it requires.
+pub(crate) fn resolve_install_order<'a>(
+    mods: &'a [ModInfo],
+    targets: &[&'a ModInfo],
+) -> Result<Vec<&'a ModInfo>, ApiError> {
+    let mut marks = HashMap::new();
+    let mut stack = Vec::new();
+    let mut order = Vec::new();
+
+    for target in targets {
+        visit(mods, target, &mut marks, &mut stack, &mut order)?;
+    }
+
+    Ok(order)
+}
+
+fn visit<'a>(
+    mods: &'a [ModInfo],
+    mod_info: &'a ModInfo,
+    marks: &mut HashMap<usize, Mark>,
+    stack: &mut Vec<&'a ModInfo>,
+    order: &mut Vec<&'a ModInfo>,
+) -> Result<(), ApiError> {
+    match marks.get(&mod_info.id) {
+        Some(Mark::Done) => return Ok(()),
+        Some(Mark::Visiting) => {
+            let start = stack
+                .iter()
+                .position(|visiting| visiting.id == mod_info.id)
+                .unwrap_or(0);
+            let cycle: Vec<&str> = stack[start..]
+                .iter()
+                .chain(std::iter::once(&mod_info))
+                .map(|visiting| visiting.name.as_str())
+                .collect();
+
+            return Err(ApiError::DependencyCycle(cycle.join(" -> ")));
+        }
+        None => {}
+    }
+
+    marks.insert(mod_info.id, Mark::Visiting);
+    stack.push(mod_info);
+
+    // Optional requirements are up to the user, only hard ones are pulled in
+    let requirements = mod_info
+        .latest_version()
+        .into_iter()
+        .flat_map(|version| version.requirements.iter())
+        .filter(|requirement| !requirement.optional);
+
+    for requirement in requirements {
+        let dependency =
+            find_requirement(mods, requirement).ok_or_else(|| ApiError::UnresolvedRequirement {
+                mod_name: mod_info.name.clone(),
+                requirement: requirement.name.clone(),
+            })?;
+
+        // Some mods list themselves, that is not a cycle worth failing over
+        if dependency.id != mod_info.id {
+            visit(mods, dependency, marks, stack, order)?;
+        }
+    }
+
+    stack.pop();
+    marks.insert(mod_info.id, Mark::Done);
+    order.push(mod_info);
+
+    Ok(())
+}
+
+/// Installed mods, other than `id` itself, whose installed version requires the mod `id`.
+pub(crate) fn installed_dependents<'a>(
+    mods: &[ModInfo],
+    installed: &'a [InstalledMod],
+    id: usize,
+) -> Vec<&'a InstalledMod> {
+    installed
+        .iter()
+        .filter(|dependent| dependent.id != id)
+        .filter(|dependent| installed_requirements(mods, dependent).contains(&id))
+        .collect()
+}
+
+/// Orders `installed` so every mod comes after the installed mods it requires.
+///
+/// Requirements that are not installed are skipped and cycles are broken where they are
+/// found, so this never fails.
+pub(crate) fn order_installed<'a>(
+    mods: &[ModInfo],
+    installed: &'a [InstalledMod],
+) -> Vec<&'a InstalledMod> {
+    let mut marks = HashMap::new();
+    let mut order = Vec::new();
+
+    for target in installed {
+        visit_installed(mods, installed, target, &mut marks, &mut order);
+    }
+
+    order
+}
+
+fn visit_installed<'a>(
+    mods: &[ModInfo],
+    installed: &'a [InstalledMod],
+    target: &'a InstalledMod,
+    marks: &mut HashMap<usize, Mark>,
+    order: &mut Vec<&'a InstalledMod>,
+) {
+    if marks.contains_key(&target.id) {
+        return;
+    }
+    marks.insert(target.id, Mark::Visiting);
+
+    for id in installed_requirements(mods, target) {
+        if let Some(dependency) = installed.iter().find(|dependency| dependency.id == id) {
+            visit_installed(mods, installed, dependency, marks, order);
+        }
+    }
+
+    marks.insert(target.id, Mark::Done);
+    order.push(target);
+}
+
+/// Catalog ids of the hard requirements of an installed mod's version on disk,
+/// the latest version's if the catalog dropped it.
+fn installed_requirements(mods: &[ModInfo], installed: &InstalledMod) -> Vec<usize> {
+    let Some(mod_info) = mods.iter().find(|mod_info| mod_info.id == installed.id) else {
+        return Vec::new();
+    };
+
+    let version = mod_info
+        .versions
+        .iter()
+        .find(|version| version.mod_version == installed.mod_version)
+        .or_else(|| mod_info.latest_version());
+
+    version
+        .into_iter()
+        .flat_map(|version| version.requirements.iter())
+        .filter(|requirement| !requirement.optional)
+        .filter_map(|requirement| find_requirement(mods, requirement))
+        .map(|required| required.id)
+        .filter(|&id| id != installed.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_install_order;
+    use crate::api::{api_error::ApiError, mod_info::test_mod, mod_info::ModInfo};
+
+    fn catalog() -> Vec<ModInfo> {
+        let mut ui = test_mod(113, "BTKUILib", "2.0.0", &[]);
+        ui.aliases = Some(vec!["BTK UI".to_string()]);
+
+        vec![
+            ui,
+            test_mod(1, "Chain", "1.0.0", &["Middle"]),
+            test_mod(2, "Middle", "1.0.0", &["BTKUILib"]),
+            test_mod(3, "Left", "1.0.0", &["BTKUILib"]),
+            test_mod(4, "Right", "1.0.0", &["btk ui"]),
+            test_mod(5, "Diamond", "1.0.0", &["Left", "Right"]),
+            test_mod(
+                6,
+                "ById",
+                "1.0.0",
+                &["[UI](https://api.cvrmg.com/v1/mods/download/113)"],
+            ),
+            test_mod(7, "Optional", "1.0.0", &["[Chatbox (Optional)](https://x)"]),
+            test_mod(8, "Itself", "1.0.0", &["Itself", "BTKUILib"]),
+            test_mod(9, "Ping", "1.0.0", &["Pong"]),
+            test_mod(10, "Pong", "1.0.0", &["Ping"]),
+            test_mod(11, "Missing", "1.0.0", &["Chatbox"]),
+        ]
+    }
+
+    #[test]
+    fn orders_requirements_first() {
+        let mods = catalog();
+
+        // (targets, expected order)
+        let cases: [(&[&str], &[&str]); 7] = [
+            (&["BTKUILib"], &["BTKUILib"]),
+            (&["Chain"], &["BTKUILib", "Middle", "Chain"]),
+            (&["Diamond"], &["BTKUILib", "Left", "Right", "Diamond"]),
+            (&["ById"], &["BTKUILib", "ById"]),
+            (&["Optional"], &["Optional"]),
+            (&["Itself"], &["BTKUILib", "Itself"]),
+            (&["Middle", "Chain"], &["BTKUILib", "Middle", "Chain"]),
+        ];
+
+        for (targets, expected) in cases {
+            let targets: Vec<&ModInfo> = targets
+                .iter()
+                .map(|name| mods.iter().find(|m| m.name == *name).unwrap())
+                .collect();
+            let order: Vec<&str> = resolve_install_order(&mods, &targets)
+                .unwrap_or_else(|err| panic!("{expected:?}: {err}"))
+                .iter()
+                .map(|mod_info| mod_info.name.as_str())
+                .collect();
+            assert_eq!(order, expected);
+        }
+    }
+
+    #[test]
+    fn reports_cycles_and_missing_requirements() {
+        let mods = catalog();
+        let find = |name: &str| mods.iter().find(|m| m.name == name).unwrap();
+
+        match resolve_install_order(&mods, &[find("Ping")]) {
+            Err(ApiError::DependencyCycle(cycle)) => assert_eq!(cycle, "Ping -> Pong -> Ping"),
+            other => panic!("expected a cycle, got {other:?}"),
+        }
+
+        match resolve_install_order(&mods, &[find("Missing")]) {
+            Err(ApiError::UnresolvedRequirement {
+                mod_name,
+                requirement,
+            }) => {
+                assert_eq!(mod_name, "Missing");
+                assert_eq!(requirement, "Chatbox");
+            }
+            other => panic!("expected an unresolved requirement, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use crate::api::{api_error::ApiError, mod_info::ModInfo};
+use crate::manifest::{InstalledMod, Manifest};
+use crate::{dependencies, utils};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+/// Folder inside `Mods`/`Plugins` whose DLLs are not loaded by the game.
+pub(crate) const DISABLED_FOLDER_NAME: &str = "Disabled";
+
+/// Catalog category of the libraries other mods build on.
+const CORE_LIBRARIES_CATEGORY: &str = "Core Mods & Libraries";
+
+/// Whether `path` points into a `Disabled` folder.
+pub(crate) fn is_disabled_path(path: &Path) -> bool {
+    path.parent()
+        .and_then(Path::file_name)
+        .is_some_and(|folder| folder == DISABLED_FOLDER_NAME)
+}
+
+/// Moves an installed mod into the `Disabled` folder next to it and records the new location,
+/// so the mod stays tracked and can be enabled again.
+///
+/// Returns the new path, or the current one if the mod already is disabled.
+pub(crate) async fn disable_mod(
+    chillout_folder: &Path,
+    installed: &InstalledMod,
+) -> Result<PathBuf, ApiError> {
+    if installed.is_disabled() {
+        return Ok(installed.path(chillout_folder));
+    }
+
+    let destination = chillout_folder
+        .join(installed.mod_type.folder_name())
+        .join(DISABLED_FOLDER_NAME);
+    move_tracked(chillout_folder, installed, &destination).await
+}
+
+/// Moves a disabled mod back into `Mods` or `Plugins` and records the new location.
+///
+/// Returns the new path, or the current one if the mod is not disabled.
+pub(crate) async fn enable_mod(
+    chillout_folder: &Path,
+    installed: &InstalledMod,
+) -> Result<PathBuf, ApiError> {
+    if !installed.is_disabled() {
+        return Ok(installed.path(chillout_folder));
+    }
+
+    let destination = chillout_folder.join(installed.mod_type.folder_name());
+    move_tracked(chillout_folder, installed, &destination).await
+}
+
+/// Moves the DLL of `installed` into `folder`, keeping its name, and updates the manifest.
+///
+/// Moves the DLL back if the manifest cannot be updated, so an error always means nothing moved.
+async fn move_tracked(
+    chillout_folder: &Path,
+    installed: &InstalledMod,
+    folder: &Path,
+) -> Result<PathBuf, ApiError> {
+    let path = installed.path(chillout_folder);
+    let file_name = path.file_name().ok_or(ApiError::InvalidFileName)?;
+    let destination = utils::move_file(&path, &folder.join(file_name)).await?;
+
+    if let Err(err) = record_move(chillout_folder, installed, &destination).await {
+        if let Err(undo_err) = utils::move_file(&destination, &path).await {
+            eprintln!(
+                "Failed to move {} back to {}: {undo_err}",
+                destination.display(),
+                path.display()
+            );
+        }
+        return Err(err);
+    }
+
+    Ok(destination)
+}
+
+/// Records `destination` as the new location of `installed` in the manifest.
+async fn record_move(
+    chillout_folder: &Path,
+    installed: &InstalledMod,
+    destination: &Path,
+) -> Result<(), ApiError> {
+    // Mods found by hash are not in the manifest yet, record them so they can be enabled again
+    let mut manifest = Manifest::load(chillout_folder).await?;
+    let mut moved = manifest
+        .get(installed.id)
+        .cloned()
+        .unwrap_or_else(|| installed.clone());
+    moved.file = destination
+        .strip_prefix(chillout_folder)
+        .unwrap_or(destination)
+        .to_path_buf();
+    manifest.insert(moved);
+    manifest.save(chillout_folder).await
+}
+
+/// Ids of the catalog's core libraries and everything they require, the mods that stay
+/// enabled when disabling everything else.
+pub(crate) fn core_library_ids(mods: &[ModInfo]) -> HashSet<usize> {
+    mods.iter()
+        .filter(|mod_info| mod_info.category.as_deref() == Some(CORE_LIBRARIES_CATEGORY))
+        .flat_map(|library| {
+            // A library with requirements missing from the catalog is still kept on its own
+            dependencies::resolve_install_order(mods, &[library]).unwrap_or_else(|_| vec![library])
+        })
+        .map(|mod_info| mod_info.id)
+        .collect()
+}
+
+/// The installed mods `disable --all-except-core` disables.
+pub(crate) fn all_except_core<'a>(
+    mods: &[ModInfo],
+    installed: &'a [InstalledMod],
+) -> Vec<&'a InstalledMod> {
+    let core = core_library_ids(mods);
+    installed
+        .iter()
+        .filter(|installed| !core.contains(&installed.id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{all_except_core, core_library_ids, disable_mod, enable_mod, is_disabled_path};
+    use crate::api::mod_info::{test_mod, ModInfo};
+    use crate::manifest::{InstalledMod, Manifest};
+    use crate::utils;
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+
+    fn installed(mod_info: &ModInfo, file: &str) -> InstalledMod {
+        InstalledMod::new(
+            mod_info,
+            &mod_info.versions[0],
+            Path::new(file),
+            Path::new(""),
+        )
+    }
+
+    async fn install(game: &Path, mod_info: &ModInfo, file: &str) -> InstalledMod {
+        let installed = installed(mod_info, file);
+        let path = installed.path(game);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &mod_info.name).unwrap();
+
+        let mut manifest = Manifest::load(game).await.unwrap();
+        manifest.insert(installed.clone());
+        manifest.save(game).await.unwrap();
+        installed
+    }
+
+    #[test]
+    fn recognizes_disabled_paths() {
+        // (path, disabled)
+        let cases = [
+            ("Mods/Disabled/Mod.dll", true),
+            ("Plugins/Disabled/Plugin.dll", true),
+            ("Mods/Mod.dll", false),
+            ("Mods/Disabled.dll", false),
+            ("Disabled/Mods/Mod.dll", false),
+        ];
+
+        for (path, disabled) in cases {
+            assert_eq!(is_disabled_path(Path::new(path)), disabled, "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn disables_and_enables_again() {
+        let game = utils::test_folder("disables_and_enables_again");
+        let mod_info = test_mod(1, "Some Mod", "1.0.0", &[]);
+        let mut target = install(&game, &mod_info, "Mods/SomeMod.dll").await;
+
+        let disabled = disable_mod(&game, &target).await.unwrap();
+        assert_eq!(disabled, game.join("Mods/Disabled/SomeMod.dll"));
+        assert!(disabled.exists());
+        assert!(!game.join("Mods/SomeMod.dll").exists());
+        let manifest = Manifest::load(&game).await.unwrap();
+        target = manifest.get(1).unwrap().clone();
+        assert_eq!(target.file, Path::new("Mods/Disabled/SomeMod.dll"));
+        assert!(target.is_disabled());
+
+        let enabled = enable_mod(&game, &target).await.unwrap();
+        assert_eq!(enabled, game.join("Mods/SomeMod.dll"));
+        assert!(enabled.exists());
+        assert!(!disabled.exists());
+        let manifest = Manifest::load(&game).await.unwrap();
+        assert_eq!(manifest.get(1).unwrap().file, Path::new("Mods/SomeMod.dll"));
+    }
+
+    #[tokio::test]
+    async fn moves_the_mod_back_when_the_manifest_fails() {
+        let game = utils::test_folder("moves_the_mod_back_when_the_manifest_fails");
+        let mod_info = test_mod(1, "Some Mod", "1.0.0", &[]);
+        let target = install(&game, &mod_info, "Mods/SomeMod.dll").await;
+        std::fs::write(crate::manifest::manifest_path(&game), "{").unwrap();
+
+        assert!(disable_mod(&game, &target).await.is_err());
+        assert!(game.join("Mods/SomeMod.dll").exists());
+        assert!(!game.join("Mods/Disabled/SomeMod.dll").exists());
+    }
+
+    #[tokio::test]
+    async fn leaves_mods_already_in_the_wanted_state() {
+        let game = utils::test_folder("leaves_mods_already_in_the_wanted_state");
+        let enabled_mod = test_mod(1, "Enabled", "1.0.0", &[]);
+        let disabled_mod = test_mod(2, "Disabled", "1.0.0", &[]);
+        let enabled = install(&game, &enabled_mod, "Mods/Enabled.dll").await;
+        let disabled = install(&game, &disabled_mod, "Plugins/Disabled/Disabled.dll").await;
+        let before = std::fs::read(crate::manifest::manifest_path(&game)).unwrap();
+
+        // (target, path returned)
+        let cases: [(&InstalledMod, PathBuf); 2] = [
+            (&enabled, game.join("Mods/Enabled.dll")),
+            (&disabled, game.join("Plugins/Disabled/Disabled.dll")),
+        ];
+        for (target, path) in cases {
+            let result = if target.is_disabled() {
+                disable_mod(&game, target).await
+            } else {
+                enable_mod(&game, target).await
+            };
+            assert_eq!(result.unwrap(), path, "{}", target.name);
+            assert!(path.exists(), "{}", target.name);
+        }
+
+        assert_eq!(
+            std::fs::read(crate::manifest::manifest_path(&game)).unwrap(),
+            before
+        );
+    }
+
+    #[test]
+    fn keeps_core_libraries_and_their_requirements() {
+        let mut library = test_mod(1, "BTKUILib", "1.0.0", &["UI Base"]);
+        library.category = Some("Core Mods & Libraries".to_string());
+        let mods = [
+            library,
+            test_mod(2, "UI Base", "1.0.0", &[]),
+            test_mod(3, "Some Mod", "1.0.0", &["BTKUILib"]),
+            test_mod(4, "Other Mod", "1.0.0", &[]),
+        ];
+        assert_eq!(core_library_ids(&mods), HashSet::from([1, 2]));
+
+        let installed: Vec<InstalledMod> = mods
+            .iter()
+            .map(|mod_info| installed(mod_info, &format!("Mods/{}.dll", mod_info.id)))
+            .collect();
+        let disabled: Vec<usize> = all_except_core(&mods, &installed)
+            .into_iter()
+            .map(|installed| installed.id)
+            .collect();
+        assert_eq!(disabled, [3, 4]);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::api::{
+    api_error::ApiError,
+    game_version::{GameBuild, GameVersion},
+};
+use crate::melon_log;
+use regex::bytes::Regex;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+// Build string as stored in the player settings, e.g. "2024r176" or "2024r176 ex1"
+static BUILD_STRING: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?-u)\b20\d\dr\d+(?:p\d+)?(?: ?ex\d+)?\b").expect("Invalid game build regex")
+});
+
+/// Where a detected game version was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum GameVersionSource {
+    /// The player settings in `ChilloutVR_Data/globalgamemanagers`.
+    GameData(PathBuf),
+    /// The header of the loader's log of the last game start.
+    MelonLoaderLog(PathBuf),
+}
+
+impl fmt::Display for GameVersionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GameData(path) | Self::MelonLoaderLog(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Game version found in a ChilloutVR installation.
+#[derive(Debug, Clone)]
+pub(crate) struct DetectedGameVersion {
+    pub(crate) version: GameVersion,
+    pub(crate) source: GameVersionSource,
+}
+
+impl DetectedGameVersion {
+    /// Whether a mod made for `game_version` works with the detected build. Unknown values on
+    /// either side are given the benefit of the doubt.
+    pub(crate) fn supports(&self, game_version: &GameVersion) -> bool {
+        match (self.version.build(), game_version) {
+            (Some(build), GameVersion::Build(_) | GameVersion::Universal) => {
+                game_version.matches(build)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Scripting backend the game was built with, it decides which runtime the loader boots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GameBackend {
+    Mono,
+    Il2Cpp,
+}
+
+impl fmt::Display for GameBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mono => f.pad("Mono"),
+            Self::Il2Cpp => f.pad("Il2Cpp"),
+        }
+    }
+}
+
+/// Tells Mono and `Il2Cpp` builds apart by the files Unity ships with each, `None` if
+/// `chillout_folder` has neither.
+pub(crate) fn detect_backend(chillout_folder: &Path) -> Result<Option<GameBackend>, ApiError> {
+    let game_data = chillout_folder.join("ChilloutVR_Data");
+
+    if chillout_folder.join("GameAssembly.dll").try_exists()?
+        || game_data.join("il2cpp_data").try_exists()?
+    {
+        return Ok(Some(GameBackend::Il2Cpp));
+    }
+    if chillout_folder.join("MonoBleedingEdge").try_exists()?
+        || game_data
+            .join("Managed")
+            .join("Assembly-CSharp.dll")
+            .try_exists()?
+    {
+        return Ok(Some(GameBackend::Mono));
+    }
+
+    Ok(None)
+}
+
+/// Detects the installed ChilloutVR build.
+///
+/// The game data is tried first since it changes with every game update, the loader log only
+/// once the game was started afterwards. `None` if neither has a build string.
+pub(crate) async fn detect_game_version(
+    chillout_folder: &Path,
+) -> Result<Option<DetectedGameVersion>, ApiError> {
+    let game_data = chillout_folder
+        .join("ChilloutVR_Data")
+        .join("globalgamemanagers");
+    if game_data.try_exists()? {
+        let data = tokio::fs::read(&game_data).await?;
+        if let Some(build) = find_build_string(&data) {
+            return Ok(Some(DetectedGameVersion {
+                version: GameVersion::Build(build),
+                source: GameVersionSource::GameData(game_data),
+            }));
+        }
+    }
+
+    let Some(log) = melon_log::read_latest_log(chillout_folder).await else {
+        return Ok(None);
+    };
+
+    Ok(melon_log::header_value(&log, "Game Version")
+        .map(GameVersion::parse)
+        .filter(|version| version.build().is_some())
+        .map(|version| DetectedGameVersion {
+            version,
+            source: GameVersionSource::MelonLoaderLog(melon_log::latest_log_path(chillout_folder)),
+        }))
+}
+
+/// Finds the build string in serialized Unity data.
+///
+/// Strings there are prefixed with their length as a 32-bit integer, a match with a correct
+/// prefix is preferred over one that merely looks like a build.
+fn find_build_string(data: &[u8]) -> Option<GameBuild> {
+    let mut fallback = None;
+
+    for found in BUILD_STRING.find_iter(data) {
+        let Some(build) = std::str::from_utf8(found.as_bytes())
+            .ok()
+            .map(GameVersion::parse)
+            .and_then(|version| version.build().copied())
+        else {
+            continue;
+        };
+
+        let length_prefixed = found
+            .start()
+            .checked_sub(4)
+            .and_then(|start| data.get(start..found.start()))
+            .is_some_and(|prefix| {
+                u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]])
+                    == u32::try_from(found.len()).unwrap_or(u32::MAX)
+            });
+        if length_prefixed {
+            return Some(build);
+        }
+
+        fallback.get_or_insert(build);
+    }
+
+    fallback
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_game_version, find_build_string, GameVersionSource};
+    use crate::api::game_version::{test_build, GameVersion};
+    use crate::utils;
+
+    /// A Unity serialized string, its length as a 32-bit integer followed by the bytes.
+    fn serialized(value: &str) -> Vec<u8> {
+        let mut data = u32::try_from(value.len()).unwrap().to_le_bytes().to_vec();
+        data.extend_from_slice(value.as_bytes());
+        data.resize(data.len().next_multiple_of(4), 0);
+        data
+    }
+
+    #[test]
+    fn finds_build_strings() {
+        let mut data = b"\x00\x01Alpha Blend Interactive\x00".to_vec();
+        data.extend(serialized("2024r176 ex1"));
+        assert_eq!(
+            find_build_string(&data),
+            Some(test_build(2024, 176, 0, Some(1)))
+        );
+
+        assert_eq!(
+            find_build_string(&serialized("2022r171p4")),
+            Some(test_build(2022, 171, 4, None))
+        );
+        assert_eq!(
+            find_build_string(b"\x002023r173\x00"),
+            Some(test_build(2023, 173, 0, None))
+        );
+        assert_eq!(find_build_string(b"no build in here, 2024r or r176"), None);
+        assert_eq!(find_build_string(b"x2024r176"), None);
+    }
+
+    #[test]
+    fn prefers_length_prefixed_build_strings() {
+        // A path or a changelog mentioning an older build comes first
+        let mut data = b"\x00\x00Assets/2023r173/".to_vec();
+        data.extend(serialized("2024r177"));
+        assert_eq!(
+            find_build_string(&data),
+            Some(test_build(2024, 177, 0, None))
+        );
+
+        // Without any prefix the first match is the best guess
+        assert_eq!(
+            find_build_string(b"\x002023r173\x00\x002024r177\x00"),
+            Some(test_build(2023, 173, 0, None))
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_game_data_first() {
+        let folder = utils::test_folder("reads_game_data_first");
+        let game_data = folder.join("ChilloutVR_Data");
+        std::fs::create_dir_all(&game_data).unwrap();
+        std::fs::write(game_data.join("globalgamemanagers"), serialized("2024r177")).unwrap();
+        std::fs::create_dir_all(folder.join("MelonLoader")).unwrap();
+        std::fs::write(
+            folder.join("MelonLoader").join("Latest.log"),
+            "[12:00:00.000] Game Version: 2024r176\n",
+        )
+        .unwrap();
+
+        let detected = detect_game_version(&folder).await.unwrap().unwrap();
+        assert_eq!(
+            detected.version,
+            GameVersion::Build(test_build(2024, 177, 0, None))
+        );
+        assert_eq!(
+            detected.source,
+            GameVersionSource::GameData(game_data.join("globalgamemanagers"))
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_loader_log() {
+        let folder = utils::test_folder("falls_back_to_the_loader_log");
+        let game_data = folder.join("ChilloutVR_Data");
+        std::fs::create_dir_all(&game_data).unwrap();
+        std::fs::write(game_data.join("globalgamemanagers"), b"no build here").unwrap();
+
+        assert!(detect_game_version(&folder).await.unwrap().is_none());
+
+        let log = folder.join("MelonLoader").join("Latest.log");
+        std::fs::create_dir_all(folder.join("MelonLoader")).unwrap();
+        std::fs::write(
+            &log,
+            "------------------------------\n\
+             [12:00:00.000] MelonLoader v0.6.1 Open-Beta\n\
+             [12:00:00.001] Game Name: ChilloutVR\n\
+             [12:00:00.002] Game Developer: Alpha Blend Interactive\n\
+             [12:00:00.003] Game Version: 2024r176 ex1\n",
+        )
+        .unwrap();
+
+        let detected = detect_game_version(&folder).await.unwrap().unwrap();
+        assert_eq!(
+            detected.version,
+            GameVersion::Build(test_build(2024, 176, 0, Some(1)))
+        );
+        assert_eq!(
+            detected.source,
+            GameVersionSource::MelonLoaderLog(log.clone())
+        );
+
+        // A header without a build is no version
+        std::fs::write(&log, "Game Version: unknown\n").unwrap();
+        assert!(detect_game_version(&folder).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::api::{
+    api_error::ApiError,
+    mod_info::ModInfo,
+    mod_version::{ApprovalStatus, ModVersion},
+};
+use crate::manifest::InstalledMod;
+use crate::outdated;
+use std::path::Path;
+
+/// An installed mod whose approval status changed in the catalog since it was installed.
+#[derive(Debug, Clone)]
+pub(crate) struct StatusChange {
+    pub(crate) installed: InstalledMod,
+    /// Status recorded at install time, `None` if the manifest predates recording it.
+    pub(crate) previous: Option<ApprovalStatus>,
+    /// Status of the installed version now, `None` if the catalog no longer lists it.
+    pub(crate) current: Option<ApprovalStatus>,
+}
+
+impl StatusChange {
+    /// Whether the mod is now Broken or Outdated and should be disabled.
+    pub(crate) fn needs_attention(&self) -> bool {
+        self.current
+            .as_ref()
+            .is_some_and(ApprovalStatus::is_flagged)
+    }
+
+    /// The current status, or that the installed version is gone from the catalog.
+    pub(crate) fn describe_current(&self) -> String {
+        self.current.as_ref().map_or_else(
+            || "installed version not in catalog".to_string(),
+            ToString::to_string,
+        )
+    }
+}
+
+/// Compares the approval status of every installed mod with the one it had at install time.
+///
+/// Mods with no recorded status are only reported when they are flagged now. Installed
+/// versions the catalog no longer lists are always reported.
+pub(crate) async fn check_health(
+    chillout_folder: &Path,
+    mods: &[ModInfo],
+) -> Result<Vec<StatusChange>, ApiError> {
+    let installed = outdated::collect_installed(chillout_folder, mods).await?;
+
+    Ok(installed
+        .into_iter()
+        .filter_map(|installed| {
+            let mod_info = mods.iter().find(|mod_info| mod_info.id == installed.id)?;
+            compare_status(mod_info, installed)
+        })
+        .collect())
+}
+
+fn compare_status(mod_info: &ModInfo, installed: InstalledMod) -> Option<StatusChange> {
+    let current =
+        installed_version(mod_info, &installed).map(|version| version.approval_status.clone());
+
+    let changed = match (&installed.approval_status, &current) {
+        (_, None) => true,
+        (Some(previous), Some(current)) => previous != current,
+        (None, Some(current)) => current.is_flagged(),
+    };
+
+    changed.then(|| StatusChange {
+        previous: installed.approval_status.clone(),
+        installed,
+        current,
+    })
+}
+
+/// The catalog entry of the installed version.
+fn installed_version<'a>(
+    mod_info: &'a ModInfo,
+    installed: &InstalledMod,
+) -> Option<&'a ModVersion> {
+    mod_info
+        .versions
+        .iter()
+        .find(|version| version.mod_version == installed.mod_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_status, StatusChange};
+    use crate::api::mod_info::test_mod;
+    use crate::api::mod_version::ApprovalStatus;
+    use crate::manifest::InstalledMod;
+    use semver::Version;
+    use std::path::Path;
+
+    #[test]
+    fn reports_status_changes() {
+        let broken = ApprovalStatus::Broken(Some("No longer works".to_string()));
+        let outdated = ApprovalStatus::Outdated(None);
+        // (status at install, installed version, catalog status, reported as)
+        let cases = [
+            (
+                Some(ApprovalStatus::Approved),
+                "1.0.0",
+                ApprovalStatus::Approved,
+                None,
+            ),
+            (
+                Some(ApprovalStatus::Approved),
+                "1.0.0",
+                broken.clone(),
+                Some("Broken (No longer works)"),
+            ),
+            (
+                Some(ApprovalStatus::Approved),
+                "1.0.0",
+                outdated.clone(),
+                Some("Outdated"),
+            ),
+            (Some(broken.clone()), "1.0.0", broken.clone(), None),
+            (None, "1.0.0", ApprovalStatus::Approved, None),
+            (None, "1.0.0", outdated.clone(), Some("Outdated")),
+            // The catalog lists 1.0.0 and 2.0.0, neither status says anything about 0.9.0
+            (
+                Some(ApprovalStatus::Approved),
+                "0.9.0",
+                broken.clone(),
+                Some("installed version not in catalog"),
+            ),
+            (
+                None,
+                "0.9.0",
+                ApprovalStatus::Approved,
+                Some("installed version not in catalog"),
+            ),
+        ];
+
+        for (previous, installed_version, status, expected) in cases {
+            let mut mod_info = test_mod(1, "Some Mod", "1.0.0", &[]);
+            mod_info.versions[0].approval_status = status;
+            // The catalog lists the newest version first
+            let mut latest = mod_info.versions[0].clone();
+            latest.mod_version = Version::new(2, 0, 0);
+            latest.approval_status = ApprovalStatus::Approved;
+            mod_info.versions.insert(0, latest);
+
+            let mut installed = InstalledMod::new(
+                &mod_info,
+                &mod_info.versions[1],
+                Path::new("Mods/SomeMod.dll"),
+                Path::new(""),
+            );
+            installed.mod_version = Version::parse(installed_version).unwrap();
+            installed.approval_status.clone_from(&previous);
+
+            let change = compare_status(&mod_info, installed);
+            let reported = change.as_ref().map(StatusChange::describe_current);
+            assert_eq!(
+                reported.as_deref(),
+                expected,
+                "{previous:?} {installed_version}"
+            );
+
+            // Only flagged statuses are disabled, a version missing from the catalog is not
+            let flagged = expected.is_some_and(|expected| {
+                expected.starts_with("Broken") || expected.starts_with("Outdated")
+            });
+            assert_eq!(
+                change.is_some_and(|change| change.needs_attention()),
+                flagged,
+                "{previous:?} {installed_version}"
+            );
+        }
+    }
+}
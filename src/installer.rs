@@ -0,0 +1,329 @@
+use crate::api::{self, api_error::ApiError, mod_info::ModInfo};
+use crate::manifest::{InstalledMod, Manifest};
+use reqwest::Client;
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{sync::Semaphore, task::JoinSet};
+
+/// Result of installing one mod of a batch.
+#[derive(Debug)]
+pub(crate) struct InstallOutcome<'a> {
+    pub(crate) mod_info: &'a ModInfo,
+    pub(crate) result: Result<PathBuf, ApiError>,
+}
+
+/// Results of installing a batch of mods.
+#[derive(Debug)]
+pub(crate) struct InstallReport<'a> {
+    /// One per mod, in the order of the batch.
+    pub(crate) outcomes: Vec<InstallOutcome<'a>>,
+    /// Why the successful installs could not be recorded in the manifest, if they could not.
+    pub(crate) manifest_error: Option<ApiError>,
+}
+
+/// Downloads the latest version of every mod in `mods` into `chillout_folder`, with at most
+/// `max_concurrent` downloads in flight over the shared `client`.
+///
+/// Mods are installed in waves, each starting once the mods it requires from the same batch
+/// are done, so requirements are in place first. A mod whose requirement failed is skipped
+/// and reported as failed. Successful installs are recorded in the manifest, a failure to
+/// do so is reported next to the outcomes, which are in the order of `mods`.
+pub(crate) async fn install_mods<'a>(
+    client: &Client,
+    mods: &[&'a ModInfo],
+    chillout_folder: &Path,
+    max_concurrent: usize,
+) -> InstallReport<'a> {
+    let semaphore = Arc::new(Semaphore::new(max_concurrent.max(1)));
+    let requirements: Vec<Vec<usize>> = mods
+        .iter()
+        .map(|mod_info| batch_requirements(mods, mod_info))
+        .collect();
+    let mut results: Vec<Option<Result<PathBuf, ApiError>>> =
+        std::iter::repeat_with(|| None).take(mods.len()).collect();
+
+    loop {
+        let done: Vec<Option<bool>> = results
+            .iter()
+            .map(|result| result.as_ref().map(Result::is_ok))
+            .collect();
+        let Some(wave) = next_wave(&requirements, &done) else {
+            break;
+        };
+
+        for (index, required) in wave.skip {
+            results[index] = Some(Err(ApiError::RequirementFailed {
+                mod_name: mods[index].name.clone(),
+                requirement: mods[required].name.clone(),
+            }));
+        }
+
+        let installed = install_wave(&wave.install, &semaphore, |index| {
+            let client = client.clone();
+            let mod_info = mods[index].clone();
+            let chillout_folder = chillout_folder.to_path_buf();
+            async move {
+                api::download_and_verify_mod_with_info(&client, &mod_info, chillout_folder).await
+            }
+        })
+        .await;
+        for (index, result) in installed {
+            results[index] = Some(result);
+        }
+    }
+
+    let outcomes: Vec<InstallOutcome> = results
+        .into_iter()
+        .zip(mods)
+        .map(|(result, mod_info)| InstallOutcome {
+            mod_info,
+            result: result.expect("Every mod is installed or skipped"),
+        })
+        .collect();
+
+    let manifest_error = record_outcomes(&outcomes, chillout_folder).await.err();
+    InstallReport {
+        outcomes,
+        manifest_error,
+    }
+}
+
+/// Indices of the mods in `batch` that the latest version of `mod_info` hard requires.
+fn batch_requirements(batch: &[&ModInfo], mod_info: &ModInfo) -> Vec<usize> {
+    mod_info
+        .latest_version()
+        .into_iter()
+        .flat_map(|version| version.requirements.iter())
+        .filter(|requirement| !requirement.optional)
+        .filter_map(|requirement| {
+            requirement
+                .mod_id
+                .and_then(|id| batch.iter().position(|required| required.id == id))
+                .or_else(|| {
+                    batch
+                        .iter()
+                        .position(|required| required.matches_name(&requirement.name))
+                })
+        })
+        .filter(|&index| batch[index].id != mod_info.id)
+        .collect()
+}
+
+/// The next wave of a batch: the mods to install now, and the mods to skip together with
+/// the requirement that failed.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Wave {
+    install: Vec<usize>,
+    skip: Vec<(usize, usize)>,
+}
+
+/// Plans the next wave from each mod's batch `requirements` and `done`, which is `None` for
+/// mods still pending and whether the install succeeded otherwise.
+///
+/// Returns `None` once nothing is pending.
+fn next_wave(requirements: &[Vec<usize>], done: &[Option<bool>]) -> Option<Wave> {
+    let pending: Vec<usize> = (0..done.len())
+        .filter(|&index| done[index].is_none())
+        .collect();
+    if pending.is_empty() {
+        return None;
+    }
+
+    let mut ready: Vec<usize> = pending
+        .iter()
+        .copied()
+        .filter(|&index| {
+            requirements[index]
+                .iter()
+                .all(|&required| done[required].is_some())
+        })
+        .collect();
+    // Mods that require each other can only go together
+    if ready.is_empty() {
+        ready = pending;
+    }
+
+    let mut wave = Wave::default();
+    for index in ready {
+        let failed = requirements[index]
+            .iter()
+            .find(|&&required| done[required] == Some(false));
+
+        match failed {
+            Some(&required) => wave.skip.push((index, required)),
+            None => wave.install.push(index),
+        }
+    }
+
+    Some(wave)
+}
+
+/// Runs `install` for every index in `indices` concurrently, as far as `semaphore` allows,
+/// returning each result with its index.
+async fn install_wave<F>(
+    indices: &[usize],
+    semaphore: &Arc<Semaphore>,
+    install: impl Fn(usize) -> F,
+) -> Vec<(usize, Result<PathBuf, ApiError>)>
+where
+    F: Future<Output = Result<PathBuf, ApiError>> + Send + 'static,
+{
+    let mut tasks = JoinSet::new();
+    let mut task_indices = HashMap::new();
+
+    for &index in indices {
+        let semaphore = Arc::clone(semaphore);
+        let install = install(index);
+
+        let handle = tasks.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("Download semaphore is never closed");
+            install.await
+        });
+        task_indices.insert(handle.id(), index);
+    }
+
+    let mut results = Vec::with_capacity(indices.len());
+    while let Some(joined) = tasks.join_next_with_id().await {
+        let (id, result) = match joined {
+            Ok((id, result)) => (id, result),
+            Err(err) => (err.id(), Err(err.into())),
+        };
+        results.push((task_indices[&id], result));
+    }
+
+    results
+}
+
+async fn record_outcomes(
+    outcomes: &[InstallOutcome<'_>],
+    chillout_folder: &Path,
+) -> Result<(), ApiError> {
+    let mut manifest = Manifest::load(chillout_folder).await?;
+    let mut changed = false;
+
+    for outcome in outcomes {
+        let (Ok(path), Some(version)) = (&outcome.result, outcome.mod_info.latest_version()) else {
+            continue;
+        };
+
+        manifest.insert(InstalledMod::new(
+            outcome.mod_info,
+            version,
+            path,
+            chillout_folder,
+        ));
+        changed = true;
+    }
+
+    if changed {
+        manifest.save(chillout_folder).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{install_wave, next_wave, Wave};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+
+    #[test]
+    fn plans_waves_after_requirements() {
+        // (requirements, done, install, skip)
+        type Case<'a> = (
+            &'a [&'a [usize]],
+            &'a [Option<bool>],
+            &'a [usize],
+            &'a [(usize, usize)],
+        );
+        let cases: [Case; 8] = [
+            // Independent mods all go in the first wave
+            (&[&[], &[]], &[None, None], &[0, 1], &[]),
+            // A chain 2 -> 1 -> 0 goes one link at a time
+            (&[&[], &[0], &[1]], &[None, None, None], &[0], &[]),
+            (&[&[], &[0], &[1]], &[Some(true), None, None], &[1], &[]),
+            // Both sides of a diamond go together once the shared requirement is in
+            (
+                &[&[], &[0], &[0], &[1, 2]],
+                &[Some(true), None, None, None],
+                &[1, 2],
+                &[],
+            ),
+            // A failed requirement skips the mods that need it
+            (
+                &[&[], &[0], &[]],
+                &[Some(false), None, None],
+                &[2],
+                &[(1, 0)],
+            ),
+            (
+                &[&[], &[0, 1], &[0]],
+                &[Some(true), Some(false), None],
+                &[2],
+                &[],
+            ),
+            // Mods that require each other go together
+            (&[&[1], &[0]], &[None, None], &[0, 1], &[]),
+            (&[&[1], &[0], &[]], &[None, None, Some(false)], &[0, 1], &[]),
+        ];
+
+        for (requirements, done, install, skip) in cases {
+            let requirements: Vec<Vec<usize>> = requirements
+                .iter()
+                .map(|indices| indices.to_vec())
+                .collect();
+            assert_eq!(
+                next_wave(&requirements, done),
+                Some(Wave {
+                    install: install.to_vec(),
+                    skip: skip.to_vec(),
+                }),
+                "{requirements:?} {done:?}"
+            );
+        }
+
+        assert_eq!(
+            next_wave(&[vec![], vec![0]], &[Some(true), Some(false)]),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn limits_concurrent_installs() {
+        let semaphore = Arc::new(Semaphore::new(2));
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+
+        let results = install_wave(&[0, 1, 2, 3, 4, 5], &semaphore, |index| {
+            let running = Arc::clone(&running);
+            let most = Arc::clone(&most);
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(PathBuf::from(format!("Mod{index}.dll")))
+            }
+        })
+        .await;
+
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+        let mut indices: Vec<usize> = results.iter().map(|(index, _)| *index).collect();
+        indices.sort_unstable();
+        assert_eq!(indices, [0, 1, 2, 3, 4, 5]);
+        for (index, result) in results {
+            assert_eq!(result.unwrap(), PathBuf::from(format!("Mod{index}.dll")));
+        }
+    }
+}
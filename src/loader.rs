@@ -0,0 +1,288 @@
+use crate::api::{
+    api_error::ApiError,
+    loader_version::{self, LoaderRequirement},
+    mod_info::ModInfo,
+};
+use crate::game::{self, GameBackend};
+use crate::manifest::InstalledMod;
+use crate::{assembly, melon_log};
+use regex::Regex;
+use semver::Version;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+// "[12:34:56.789] MelonLoader v0.6.1 Open-Beta"
+static LOG_LOADER_VERSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^(?:\[[^\]]*\]\s*)?MelonLoader\s+(?P<version>v?\d+(?:\.\d+)*[^\r\n]*)$")
+        .expect("Invalid log loader version regex")
+});
+
+/// The proxy DLL the game loads, which boots the loader.
+const PROXY_DLL: &str = "version.dll";
+const LOADER_FOLDER: &str = "MelonLoader";
+
+/// Runtime the loader's managed side is built for, since 0.6 both ship side by side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LoaderRuntime {
+    Net35,
+    Net6,
+}
+
+impl LoaderRuntime {
+    /// The runtime the loader boots on a game built with `backend`.
+    pub(crate) fn for_backend(backend: GameBackend) -> Self {
+        match backend {
+            GameBackend::Mono => Self::Net35,
+            GameBackend::Il2Cpp => Self::Net6,
+        }
+    }
+
+    fn folder_name(self) -> &'static str {
+        match self {
+            Self::Net35 => "net35",
+            Self::Net6 => "net6",
+        }
+    }
+}
+
+impl fmt::Display for LoaderRuntime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.folder_name())
+    }
+}
+
+/// Where the installed loader version was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LoaderVersionSource {
+    /// The assembly version of `MelonLoader.dll`.
+    Assembly(PathBuf),
+    /// The file version resource of the proxy DLL.
+    VersionResource(PathBuf),
+    /// The header of the log of the last game start.
+    Log(PathBuf),
+}
+
+impl fmt::Display for LoaderVersionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Assembly(path) | Self::VersionResource(path) | Self::Log(path) => {
+                write!(f, "{}", path.display())
+            }
+        }
+    }
+}
+
+/// What was found of a `MelonLoader` install in a ChilloutVR folder.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LoaderInstall {
+    pub(crate) version: Option<(Version, LoaderVersionSource)>,
+    /// `None` for the single runtime layout before 0.6.
+    pub(crate) runtime: Option<LoaderRuntime>,
+    /// Files the loader needs to start that are not there.
+    pub(crate) missing: Vec<PathBuf>,
+    /// Files that are there but could not be read.
+    pub(crate) corrupt: Vec<PathBuf>,
+}
+
+impl LoaderInstall {
+    /// Whether files are missing or damaged, so the game would start without mods.
+    pub(crate) fn is_broken(&self) -> bool {
+        !self.missing.is_empty() || !self.corrupt.is_empty()
+    }
+}
+
+/// Inspects the `MelonLoader` install in `chillout_folder`, `None` if none of its files are
+/// there.
+///
+/// The version comes from the first source that has one: the `MelonLoader.dll` assembly,
+/// the proxy DLL's version resource, then the log of the last game start.
+pub(crate) async fn detect_loader(
+    chillout_folder: &Path,
+) -> Result<Option<LoaderInstall>, ApiError> {
+    let Some(mut install) = inspect_loader_files(chillout_folder).await? else {
+        return Ok(None);
+    };
+
+    if install.version.is_none() {
+        install.version = log_loader_version(chillout_folder).await.map(|version| {
+            (
+                version,
+                LoaderVersionSource::Log(melon_log::latest_log_path(chillout_folder)),
+            )
+        });
+    }
+
+    Ok(Some(install))
+}
+
+/// Like `detect_loader`, but only reads the loader's own files. For loader files that never
+/// ran, such as an extracted release archive, which have no log to fall back on.
+pub(crate) async fn inspect_loader_files(folder: &Path) -> Result<Option<LoaderInstall>, ApiError> {
+    let proxy = folder.join(PROXY_DLL);
+    let loader_folder = folder.join(LOADER_FOLDER);
+    if !proxy.try_exists()? && !loader_folder.try_exists()? {
+        return Ok(None);
+    }
+
+    let mut install = LoaderInstall::default();
+
+    for required in [
+        proxy.clone(),
+        loader_folder.join("Dependencies").join("Bootstrap.dll"),
+    ] {
+        if !required.try_exists()? {
+            install.missing.push(required);
+        }
+    }
+
+    // The game's backend decides the runtime the loader boots, not which folders exist
+    let mut assembly = None;
+    if let Some(&shipped) = runtime_folders(&loader_folder)?.first() {
+        let runtime = game::detect_backend(folder)?.map_or(shipped, LoaderRuntime::for_backend);
+        install.runtime = Some(runtime);
+        assembly = Some(
+            loader_folder
+                .join(runtime.folder_name())
+                .join("MelonLoader.dll"),
+        );
+    }
+    let legacy = loader_folder.join("MelonLoader.dll");
+    if assembly.is_none() && legacy.try_exists()? {
+        assembly = Some(legacy);
+    }
+
+    match &assembly {
+        Some(path) if !path.try_exists()? => install.missing.push(path.clone()),
+        Some(path) => match assembly::read_assembly_info_from_file(path).await {
+            Ok(info) => {
+                install.version = info
+                    .assembly_version
+                    .map(|[major, minor, patch, _]| {
+                        Version::new(major.into(), minor.into(), patch.into())
+                    })
+                    .map(|version| (version, LoaderVersionSource::Assembly(path.clone())));
+            }
+            Err(ApiError::InvalidAssembly(_)) => install.corrupt.push(path.clone()),
+            Err(err) => return Err(err),
+        },
+        None => install.missing.push(loader_folder.join("MelonLoader.dll")),
+    }
+
+    if install.version.is_none() && proxy.try_exists()? {
+        let data = tokio::fs::read(&proxy).await?;
+        install.version = assembly::read_file_version(&data)
+            .filter(|version| version.iter().any(|&part| part != 0))
+            .map(|[major, minor, patch, _]| Version::new(major.into(), minor.into(), patch.into()))
+            .map(|version| (version, LoaderVersionSource::VersionResource(proxy.clone())));
+    }
+
+    Ok(Some(install))
+}
+
+/// Runtimes the loader in `loader_folder` ships, the 0.6 layout has a folder with a
+/// `MelonLoader.dll` for each.
+/// `Net35` comes first, ChilloutVR is a Mono game.
+fn runtime_folders(loader_folder: &Path) -> Result<Vec<LoaderRuntime>, ApiError> {
+    let mut runtimes = Vec::new();
+    for runtime in [LoaderRuntime::Net35, LoaderRuntime::Net6] {
+        let assembly = loader_folder
+            .join(runtime.folder_name())
+            .join("MelonLoader.dll");
+        if assembly.try_exists()? {
+            runtimes.push(runtime);
+        }
+    }
+    Ok(runtimes)
+}
+
+/// Version of the `MelonLoader` installed in `chillout_folder`, `None` if it is not
+/// installed or its version cannot be told.
+pub(crate) async fn installed_loader_version(chillout_folder: &Path) -> Option<Version> {
+    let install = detect_loader(chillout_folder).await.ok()??;
+    install.version.map(|(version, _)| version)
+}
+
+/// Installed mods whose version on disk, or the latest one if the catalog dropped it, does
+/// not work with the loader `version`, together with the loader version they ask for.
+pub(crate) fn incompatible_mods<'a, 'b>(
+    mods: &'b [ModInfo],
+    installed: &'a [InstalledMod],
+    version: &Version,
+) -> Vec<(&'a InstalledMod, &'b LoaderRequirement)> {
+    installed
+        .iter()
+        .filter_map(|installed| {
+            let mod_info = mods.iter().find(|mod_info| mod_info.id == installed.id)?;
+            let mod_version = mod_info
+                .versions
+                .iter()
+                .find(|version| version.mod_version == installed.mod_version)
+                .or_else(|| mod_info.latest_version())?;
+
+            (!mod_version.loader_version.matches(version))
+                .then_some((installed, &mod_version.loader_version))
+        })
+        .collect()
+}
+
+async fn log_loader_version(chillout_folder: &Path) -> Option<Version> {
+    let log = melon_log::read_latest_log(chillout_folder).await?;
+    let captures = LOG_LOADER_VERSION.captures(&log)?;
+    loader_version::parse_loader_version(&captures["version"])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_loader, LoaderRuntime};
+    use crate::utils;
+    use std::path::Path;
+
+    fn touch(path: &Path) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, b"").unwrap();
+    }
+
+    #[tokio::test]
+    async fn picks_the_runtime_of_the_game_backend() {
+        let folder = utils::test_folder("picks_the_runtime_of_the_game_backend");
+        touch(&folder.join("version.dll"));
+        touch(&folder.join("MelonLoader/Dependencies/Bootstrap.dll"));
+        touch(&folder.join("MelonLoader/net35/MelonLoader.dll"));
+        touch(&folder.join("MelonLoader/net6/MelonLoader.dll"));
+
+        // Both runtimes shipped, ChilloutVR is a Mono game
+        touch(&folder.join("ChilloutVR_Data/Managed/Assembly-CSharp.dll"));
+        let install = detect_loader(&folder).await.unwrap().unwrap();
+        assert_eq!(install.runtime, Some(LoaderRuntime::Net35));
+
+        touch(&folder.join("GameAssembly.dll"));
+        let install = detect_loader(&folder).await.unwrap().unwrap();
+        assert_eq!(install.runtime, Some(LoaderRuntime::Net6));
+    }
+
+    #[tokio::test]
+    async fn reports_the_missing_runtime_of_the_game() {
+        let folder = utils::test_folder("reports_the_missing_runtime_of_the_game");
+        touch(&folder.join("version.dll"));
+        touch(&folder.join("MelonLoader/Dependencies/Bootstrap.dll"));
+        touch(&folder.join("MelonLoader/net6/MelonLoader.dll"));
+        touch(&folder.join("MonoBleedingEdge/EmbedRuntime/mono-2.0-bdwgc.dll"));
+
+        let install = detect_loader(&folder).await.unwrap().unwrap();
+        assert_eq!(install.runtime, Some(LoaderRuntime::Net35));
+        assert_eq!(
+            install.missing,
+            [folder.join("MelonLoader/net35/MelonLoader.dll")]
+        );
+        assert!(install.is_broken());
+
+        // Without game files, such as a freshly extracted archive, the shipped one is used
+        std::fs::remove_dir_all(folder.join("MonoBleedingEdge")).unwrap();
+        let install = detect_loader(&folder).await.unwrap().unwrap();
+        assert_eq!(install.runtime, Some(LoaderRuntime::Net6));
+        assert!(install.missing.is_empty());
+    }
+}
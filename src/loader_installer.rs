@@ -0,0 +1,792 @@
+use crate::api::{self, api_error::ApiError};
+use crate::{loader, sha256_hasher};
+use reqwest::Client;
+use semver::Version;
+use std::{
+    fmt, fs,
+    io::{self, Cursor},
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio_util::bytes::Bytes;
+
+/// Top-level entries of a release archive that belong in the ChilloutVR folder, anything
+/// else in the archive is left out.
+pub(crate) const LOADER_ENTRIES: [&str; 3] = ["version.dll", "dobby.dll", "MelonLoader"];
+
+/// Extraction happens here first, so a bad archive never leaves half a loader behind.
+const STAGING_FOLDER_NAME: &str = ".CVRModManager-loader";
+/// The replaced loader waits here until the new one is in place.
+const PREVIOUS_FOLDER_NAME: &str = ".CVRModManager-loader-previous";
+/// Configuration and logs of replaced loaders, one folder per switch.
+const BACKUP_FOLDER_NAME: &str = "CVRModManager-backups";
+/// Files in the `MelonLoader` folder that are kept when the loader is replaced.
+const SETTINGS_EXTENSIONS: [&str; 6] = ["cfg", "ini", "json", "toml", "txt", "log"];
+
+/// Where a `MelonLoader` release archive comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LoaderArchive {
+    Url(String),
+    File(PathBuf),
+}
+
+impl LoaderArchive {
+    /// Treats `http://` and `https://` sources as URLs and everything else as a path.
+    pub(crate) fn from_source(source: &str) -> Self {
+        let source = source.trim();
+        if source.starts_with("http://") || source.starts_with("https://") {
+            Self::Url(source.to_string())
+        } else {
+            Self::File(PathBuf::from(source))
+        }
+    }
+
+    async fn read(&self, client: &Client) -> Result<Bytes, ApiError> {
+        match self {
+            Self::Url(url) => api::download_bytes(client, url).await,
+            Self::File(path) => Ok(Bytes::from(tokio::fs::read(path).await?)),
+        }
+    }
+}
+
+impl fmt::Display for LoaderArchive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url(url) => write!(f, "{url}"),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Installs `MelonLoader` into `chillout_folder` from a release archive whose SHA-256 is
+/// `expected_hash`, returning the files and folders it added.
+///
+/// Fails without touching the folder if a loader file is already there. If moving the
+/// extracted files into place fails, the ones already moved are removed again.
+pub(crate) async fn install_loader(
+    client: &Client,
+    chillout_folder: &Path,
+    archive: &LoaderArchive,
+    expected_hash: &str,
+) -> Result<Vec<PathBuf>, ApiError> {
+    if let Some(existing) = existing_entries(chillout_folder)?.into_iter().next() {
+        return Err(ApiError::LoaderAlreadyInstalled(
+            existing.display().to_string(),
+        ));
+    }
+
+    let staged = stage_loader(client, chillout_folder, archive, expected_hash).await?;
+    let result = move_into_place(&staged.staging, chillout_folder).await;
+    staged.discard().await;
+    result
+}
+
+/// A verified release archive extracted next to the game, not in place yet.
+#[derive(Debug)]
+pub(crate) struct StagedLoader {
+    staging: PathBuf,
+}
+
+impl StagedLoader {
+    /// Version of the extracted loader, `None` if its files do not tell.
+    pub(crate) async fn version(&self) -> Result<Option<Version>, ApiError> {
+        let install = loader::inspect_loader_files(&self.staging).await?;
+        Ok(install.and_then(|install| install.version.map(|(version, _)| version)))
+    }
+
+    /// Removes the extracted files.
+    pub(crate) async fn discard(self) {
+        let _ = tokio::fs::remove_dir_all(&self.staging).await;
+    }
+}
+
+/// Reads the archive, checks it against `expected_hash` and extracts it into a staging
+/// folder inside `chillout_folder`, so the later moves stay on one filesystem.
+pub(crate) async fn stage_loader(
+    client: &Client,
+    chillout_folder: &Path,
+    archive: &LoaderArchive,
+    expected_hash: &str,
+) -> Result<StagedLoader, ApiError> {
+    let data = archive.read(client).await?;
+    if !sha256_hasher::matches_hash(&data, expected_hash) {
+        return Err(ApiError::InvalidFileHash);
+    }
+
+    let staged = StagedLoader {
+        staging: chillout_folder.join(STAGING_FOLDER_NAME),
+    };
+    if staged.staging.try_exists()? {
+        tokio::fs::remove_dir_all(&staged.staging).await?;
+    }
+
+    let target = staged.staging.clone();
+    let extracted = match tokio::task::spawn_blocking(move || extract(&data, &target)).await {
+        Ok(result) => result,
+        Err(err) => Err(err.into()),
+    };
+    if let Err(err) = extracted.and_then(|()| check_staged(&staged.staging)) {
+        staged.discard().await;
+        return Err(err);
+    }
+
+    Ok(staged)
+}
+
+fn check_staged(staging: &Path) -> Result<(), ApiError> {
+    for required in ["version.dll", "MelonLoader"] {
+        if !staging.join(required).try_exists()? {
+            return Err(ApiError::InvalidLoaderArchive(format!("no {required}")));
+        }
+    }
+
+    Ok(())
+}
+
+/// Loader files and folders present in `chillout_folder`.
+fn existing_entries(chillout_folder: &Path) -> Result<Vec<PathBuf>, ApiError> {
+    let mut existing = Vec::new();
+    for entry in LOADER_ENTRIES {
+        let path = chillout_folder.join(entry);
+        if path.try_exists()? {
+            existing.push(path);
+        }
+    }
+
+    Ok(existing)
+}
+
+/// Moves the loader entries of `from` into `to`, moving the ones already moved back if
+/// one fails.
+async fn move_into_place(from: &Path, to: &Path) -> Result<Vec<PathBuf>, ApiError> {
+    let mut moved = Vec::new();
+    if let Err(err) = move_entries(from, to, &mut moved).await {
+        move_back(from, &moved).await;
+        return Err(err);
+    }
+
+    Ok(moved)
+}
+
+/// Moves the loader entries of `from` into `to`, recording each one moved in `moved`.
+async fn move_entries(from: &Path, to: &Path, moved: &mut Vec<PathBuf>) -> Result<(), ApiError> {
+    for entry in LOADER_ENTRIES {
+        let source = from.join(entry);
+        if !source.try_exists()? {
+            continue;
+        }
+
+        let destination = to.join(entry);
+        tokio::fs::rename(&source, &destination).await?;
+        moved.push(destination);
+    }
+
+    Ok(())
+}
+
+/// Undoes an interrupted `move_into_place`, carrying on past failures.
+async fn move_back(from: &Path, moved: &[PathBuf]) {
+    for path in moved.iter().rev() {
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+
+        if let Err(err) = tokio::fs::rename(path, from.join(name)).await {
+            eprintln!("Failed to move {} back: {err}", path.display());
+        }
+    }
+}
+
+/// Result of replacing the installed loader.
+#[derive(Debug)]
+pub(crate) struct SwitchOutcome {
+    /// Files and folders of the new loader.
+    pub(crate) installed: Vec<PathBuf>,
+    /// Configuration and logs of the previous loader, `None` if there was none.
+    pub(crate) backup: Option<PathBuf>,
+}
+
+/// Replaces the loader in `chillout_folder` with `staged`.
+///
+/// Only `version.dll`, `dobby.dll` and the `MelonLoader` folder are replaced, `Mods`,
+/// `Plugins` and `UserData` are never touched. The configuration and logs of the previous
+/// loader are copied into a backup folder first. The previous loader is then moved aside
+/// and put back if the new one cannot be moved into place, and a previous loader left over
+/// from an earlier switch is never overwritten. Once the new loader is in place the
+/// previous one is deleted.
+pub(crate) async fn switch_loader(
+    chillout_folder: &Path,
+    staged: StagedLoader,
+) -> Result<SwitchOutcome, ApiError> {
+    // Left behind when restoring failed, it may hold the only copy of the old loader
+    let previous = chillout_folder.join(PREVIOUS_FOLDER_NAME);
+    if previous.try_exists()? {
+        staged.discard().await;
+        return Err(ApiError::PreviousLoaderLeftOver(
+            previous.display().to_string(),
+        ));
+    }
+
+    let had_loader = !existing_entries(chillout_folder)?.is_empty();
+    let backup = if had_loader {
+        match back_up_settings(chillout_folder).await {
+            Ok(backup) => Some(backup),
+            Err(err) => {
+                staged.discard().await;
+                return Err(err);
+            }
+        }
+    } else {
+        None
+    };
+
+    let swapped = swap_loader(chillout_folder, &staged, &previous, had_loader).await;
+    staged.discard().await;
+    let installed = match swapped {
+        Ok(installed) => installed,
+        Err(err) => {
+            if let Some(backup) = &backup {
+                discard_backup(chillout_folder, backup).await;
+            }
+            return Err(err);
+        }
+    };
+
+    if had_loader {
+        if let Err(err) = tokio::fs::remove_dir_all(&previous).await {
+            eprintln!(
+                "Failed to remove the previous loader from {}, delete it before switching again: {err}",
+                previous.display()
+            );
+        }
+    }
+
+    Ok(SwitchOutcome { installed, backup })
+}
+
+/// Moves the loader in `chillout_folder` aside into `previous` and the staged one into its
+/// place, putting the previous loader back if that fails. Returns the new loader's entries.
+async fn swap_loader(
+    chillout_folder: &Path,
+    staged: &StagedLoader,
+    previous: &Path,
+    had_loader: bool,
+) -> Result<Vec<PathBuf>, ApiError> {
+    if had_loader {
+        tokio::fs::create_dir_all(previous).await?;
+        if let Err(err) = move_into_place(chillout_folder, previous).await {
+            // Empty once everything is moved back, left behind it would block every later switch
+            let _ = tokio::fs::remove_dir(previous).await;
+            return Err(err);
+        }
+    }
+
+    let mut moved = Vec::new();
+    let Err(err) = move_entries(&staged.staging, chillout_folder, &mut moved).await else {
+        return Ok(moved);
+    };
+
+    // The new entries are still in the way of the previous ones, and the staging folder
+    // is discarded anyway
+    for path in &moved {
+        let removed = if path.is_dir() {
+            tokio::fs::remove_dir_all(path).await
+        } else {
+            tokio::fs::remove_file(path).await
+        };
+        if let Err(remove_err) = removed {
+            eprintln!("Failed to remove {}: {remove_err}", path.display());
+        }
+    }
+
+    if had_loader {
+        if let Err(restore_err) = move_into_place(previous, chillout_folder).await {
+            eprintln!(
+                "Failed to restore the previous loader from {}: {restore_err}",
+                previous.display()
+            );
+            return Err(err);
+        }
+        let _ = tokio::fs::remove_dir_all(previous).await;
+    }
+
+    Err(err)
+}
+
+/// Copies the configuration files and logs of the loader in `chillout_folder` into a new
+/// backup folder, removing it again if copying fails.
+async fn back_up_settings(chillout_folder: &Path) -> Result<PathBuf, ApiError> {
+    let backup = chillout_folder
+        .join(BACKUP_FOLDER_NAME)
+        .join(format!("MelonLoader-{}", unix_now()));
+    tokio::fs::create_dir_all(&backup).await?;
+
+    if let Err(err) = copy_settings(&chillout_folder.join("MelonLoader"), &backup).await {
+        discard_backup(chillout_folder, &backup).await;
+        return Err(err);
+    }
+
+    Ok(backup)
+}
+
+/// Removes a backup of a switch that did not happen, and the backups folder if it was the
+/// only one.
+async fn discard_backup(chillout_folder: &Path, backup: &Path) {
+    let _ = tokio::fs::remove_dir_all(backup).await;
+    let _ = tokio::fs::remove_dir(chillout_folder.join(BACKUP_FOLDER_NAME)).await;
+}
+
+async fn copy_settings(loader: &Path, backup: &Path) -> Result<(), ApiError> {
+    if !loader.try_exists()? {
+        return Ok(());
+    }
+
+    let mut entries = tokio::fs::read_dir(loader).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let file_type = entry.file_type().await?;
+
+        if file_type.is_dir() && entry.file_name() == "Logs" {
+            copy_folder(&path, &backup.join("Logs")).await?;
+        } else if file_type.is_file() && is_setting_or_log(&path) {
+            tokio::fs::copy(&path, backup.join(entry.file_name())).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_setting_or_log(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            SETTINGS_EXTENSIONS
+                .iter()
+                .any(|setting| ext.eq_ignore_ascii_case(setting))
+        })
+}
+
+async fn copy_folder(from: &Path, to: &Path) -> Result<(), ApiError> {
+    let mut pending = vec![(from.to_path_buf(), to.to_path_buf())];
+
+    while let Some((from, to)) = pending.pop() {
+        tokio::fs::create_dir_all(&to).await?;
+        let mut entries = tokio::fs::read_dir(&from).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let destination = to.join(entry.file_name());
+            if entry.file_type().await?.is_dir() {
+                pending.push((entry.path(), destination));
+            } else {
+                tokio::fs::copy(entry.path(), destination).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Unpacks the loader entries of the archive in `data` into `target`.
+fn extract(data: &[u8], target: &Path) -> Result<(), ApiError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        // Entries that would land outside the target folder are never extracted
+        let Some(name) = file.enclosed_name() else {
+            continue;
+        };
+        let is_loader_entry = name.components().next().is_some_and(|first| {
+            matches!(first, Component::Normal(first) if LOADER_ENTRIES.iter().any(|entry| first == *entry))
+        });
+        if !is_loader_entry {
+            continue;
+        }
+
+        let path = target.join(name);
+        if file.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut file, &mut fs::File::create(&path)?)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        install_loader, stage_loader, swap_loader, switch_loader, LoaderArchive,
+        BACKUP_FOLDER_NAME, PREVIOUS_FOLDER_NAME, STAGING_FOLDER_NAME,
+    };
+    use crate::api::api_error::ApiError;
+    use crate::{sha256_hasher, utils};
+    use reqwest::Client;
+    use std::{
+        io::{Cursor, Write},
+        path::{Path, PathBuf},
+    };
+    use tokio_util::bytes::Bytes;
+    use zip::write::SimpleFileOptions;
+
+    /// A release archive with the loader files plus the extras real releases carry.
+    const RELEASE: [(&str, &[u8]); 5] = [
+        ("version.dll", b"proxy"),
+        ("dobby.dll", b"dobby"),
+        ("MelonLoader/Dependencies/Bootstrap.dll", b"bootstrap"),
+        ("MelonLoader/net35/MelonLoader.dll", b"loader"),
+        ("NOTICE.txt", b"notice"),
+    ];
+
+    fn build_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in entries {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// Writes the archive next to the game folder and returns it with its hash.
+    fn write_archive(folder: &Path, entries: &[(&str, &[u8])]) -> (LoaderArchive, String) {
+        let data = build_zip(entries);
+        let path = folder.join("MelonLoader.x64.zip");
+        std::fs::write(&path, &data).unwrap();
+        let hash = sha256_hasher::compute_sha256_hash(&Bytes::from(data));
+        (LoaderArchive::File(path), hash)
+    }
+
+    /// An empty game folder inside a fresh test folder, archives go next to it.
+    fn game_folder(name: &str) -> (PathBuf, PathBuf) {
+        let root = utils::test_folder(name);
+        let game = root.join("ChilloutVR");
+        std::fs::create_dir_all(&game).unwrap();
+        (root, game)
+    }
+
+    fn entries(folder: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(folder)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn installs_the_loader_entries() {
+        let (root, game) = game_folder("installs_the_loader_entries");
+        let (archive, hash) = write_archive(&root, &RELEASE);
+
+        let installed = install_loader(&Client::new(), &game, &archive, &hash)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            installed,
+            ["version.dll", "dobby.dll", "MelonLoader"].map(|entry| game.join(entry))
+        );
+        assert_eq!(entries(&game), ["MelonLoader", "dobby.dll", "version.dll"]);
+        assert_eq!(
+            std::fs::read(game.join("MelonLoader/net35/MelonLoader.dll")).unwrap(),
+            b"loader"
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_a_mismatching_hash() {
+        let (root, game) = game_folder("refuses_a_mismatching_hash");
+        let (archive, _) = write_archive(&root, &RELEASE);
+        let other_hash = sha256_hasher::compute_sha256_hash(&Bytes::from_static(b"other"));
+
+        let result = install_loader(&Client::new(), &game, &archive, &other_hash).await;
+
+        assert!(matches!(result, Err(ApiError::InvalidFileHash)));
+        assert!(entries(&game).is_empty());
+    }
+
+    #[tokio::test]
+    async fn skips_entries_outside_the_folder() {
+        let (root, game) = game_folder("skips_entries_outside_the_folder");
+        let mut release = RELEASE.to_vec();
+        release.push(("../evil.txt", b"evil"));
+        release.push(("MelonLoader/../../evil.dll", b"evil"));
+        let (archive, hash) = write_archive(&root, &release);
+
+        install_loader(&Client::new(), &game, &archive, &hash)
+            .await
+            .unwrap();
+
+        assert_eq!(entries(&game), ["MelonLoader", "dobby.dll", "version.dll"]);
+        assert_eq!(entries(&root), ["ChilloutVR", "MelonLoader.x64.zip"]);
+        assert_eq!(
+            entries(&game.join("MelonLoader")),
+            ["Dependencies", "net35"]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_archives_without_the_loader() {
+        let (root, game) = game_folder("rejects_archives_without_the_loader");
+
+        for (missing, release) in [
+            ("version.dll", &RELEASE[1..]),
+            ("MelonLoader", &[RELEASE[0], RELEASE[1], RELEASE[4]][..]),
+        ] {
+            let (archive, hash) = write_archive(&root, release);
+            let result = stage_loader(&Client::new(), &game, &archive, &hash).await;
+
+            match result {
+                Err(ApiError::InvalidLoaderArchive(reason)) => {
+                    assert_eq!(reason, format!("no {missing}"));
+                }
+                other => panic!("expected an invalid archive, got {other:?}"),
+            }
+            assert!(!game.join(STAGING_FOLDER_NAME).exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_to_overwrite_an_installed_loader() {
+        let (root, game) = game_folder("refuses_to_overwrite_an_installed_loader");
+        let (archive, hash) = write_archive(&root, &RELEASE);
+        std::fs::create_dir_all(game.join("MelonLoader")).unwrap();
+        std::fs::write(game.join("MelonLoader/Latest.log"), b"log").unwrap();
+
+        let result = install_loader(&Client::new(), &game, &archive, &hash).await;
+
+        match result {
+            Err(ApiError::LoaderAlreadyInstalled(existing)) => {
+                assert_eq!(existing, game.join("MelonLoader").display().to_string());
+            }
+            other => panic!("expected a refusal, got {other:?}"),
+        }
+        assert_eq!(entries(&game), ["MelonLoader"]);
+        assert_eq!(entries(&game.join("MelonLoader")), ["Latest.log"]);
+    }
+
+    /// A 0.5 style loader with settings and logs, plus mods and user data around it.
+    fn install_old_loader(game: &Path) {
+        for (path, data) in [
+            ("version.dll", &b"old proxy"[..]),
+            ("MelonLoader/MelonLoader.dll", b"old loader"),
+            ("MelonLoader/Loader.cfg", b"[settings]"),
+            ("MelonLoader/Latest.log", b"old log"),
+            ("MelonLoader/Logs/2024-01-01.log", b"older log"),
+            ("Mods/SomeMod.dll", b"mod"),
+            ("Plugins/SomePlugin.dll", b"plugin"),
+            ("UserData/MelonPreferences.cfg", b"[prefs]"),
+        ] {
+            let path = game.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, data).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn switches_and_backs_up_settings() {
+        let (root, game) = game_folder("switches_and_backs_up_settings");
+        install_old_loader(&game);
+        let (archive, hash) = write_archive(&root, &RELEASE);
+
+        let staged = stage_loader(&Client::new(), &game, &archive, &hash)
+            .await
+            .unwrap();
+        let outcome = switch_loader(&game, staged).await.unwrap();
+
+        assert_eq!(std::fs::read(game.join("version.dll")).unwrap(), b"proxy");
+        assert!(!game.join("MelonLoader/MelonLoader.dll").exists());
+        assert!(!game.join("MelonLoader/Loader.cfg").exists());
+        assert_eq!(
+            entries(&game),
+            [
+                BACKUP_FOLDER_NAME,
+                "MelonLoader",
+                "Mods",
+                "Plugins",
+                "UserData",
+                "dobby.dll",
+                "version.dll"
+            ]
+        );
+
+        // Mods and user data stay where they are
+        assert_eq!(
+            std::fs::read(game.join("Mods/SomeMod.dll")).unwrap(),
+            b"mod"
+        );
+        assert_eq!(
+            std::fs::read(game.join("Plugins/SomePlugin.dll")).unwrap(),
+            b"plugin"
+        );
+        assert_eq!(
+            std::fs::read(game.join("UserData/MelonPreferences.cfg")).unwrap(),
+            b"[prefs]"
+        );
+
+        // Settings and logs of the old loader are kept, its binaries are not
+        let backup = outcome.backup.unwrap();
+        assert!(backup.starts_with(game.join(BACKUP_FOLDER_NAME)));
+        assert_eq!(entries(&backup), ["Latest.log", "Loader.cfg", "Logs"]);
+        assert_eq!(
+            std::fs::read(backup.join("Loader.cfg")).unwrap(),
+            b"[settings]"
+        );
+        assert_eq!(
+            std::fs::read(backup.join("Logs/2024-01-01.log")).unwrap(),
+            b"older log"
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_to_switch_over_a_leftover_previous_loader() {
+        let (root, game) = game_folder("refuses_to_switch_over_a_leftover_previous_loader");
+        install_old_loader(&game);
+        let leftover = game.join(PREVIOUS_FOLDER_NAME);
+        std::fs::create_dir_all(leftover.join("MelonLoader")).unwrap();
+        std::fs::write(leftover.join("MelonLoader/Loader.cfg"), b"[leftover]").unwrap();
+        let (archive, hash) = write_archive(&root, &RELEASE);
+
+        let staged = stage_loader(&Client::new(), &game, &archive, &hash)
+            .await
+            .unwrap();
+        let result = switch_loader(&game, staged).await;
+
+        match result {
+            Err(ApiError::PreviousLoaderLeftOver(folder)) => {
+                assert_eq!(folder, leftover.display().to_string());
+            }
+            other => panic!("expected a refusal, got {other:?}"),
+        }
+        assert_eq!(
+            std::fs::read(leftover.join("MelonLoader/Loader.cfg")).unwrap(),
+            b"[leftover]"
+        );
+        assert_eq!(
+            std::fs::read(game.join("version.dll")).unwrap(),
+            b"old proxy"
+        );
+        assert!(!game.join(STAGING_FOLDER_NAME).exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn restores_the_previous_loader_when_switching_fails() {
+        let (root, game) = game_folder("restores_the_previous_loader_when_switching_fails");
+        install_old_loader(&game);
+        let (archive, hash) = write_archive(&root, &RELEASE);
+        let staged = stage_loader(&Client::new(), &game, &archive, &hash)
+            .await
+            .unwrap();
+
+        // A dangling link is not a loader file to move aside, but a directory cannot replace
+        // it, so moving the new loader fails at `dobby.dll` after `version.dll` was moved
+        std::fs::remove_file(staged.staging.join("dobby.dll")).unwrap();
+        std::fs::create_dir(staged.staging.join("dobby.dll")).unwrap();
+        std::os::unix::fs::symlink(root.join("nowhere"), game.join("dobby.dll")).unwrap();
+
+        let result = switch_loader(&game, staged).await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            std::fs::read(game.join("version.dll")).unwrap(),
+            b"old proxy"
+        );
+        assert_eq!(
+            std::fs::read(game.join("MelonLoader/MelonLoader.dll")).unwrap(),
+            b"old loader"
+        );
+        assert_eq!(
+            std::fs::read(game.join("MelonLoader/Loader.cfg")).unwrap(),
+            b"[settings]"
+        );
+        assert_eq!(
+            entries(&game),
+            [
+                "MelonLoader",
+                "Mods",
+                "Plugins",
+                "UserData",
+                "dobby.dll",
+                "version.dll"
+            ]
+        );
+        assert_eq!(
+            std::fs::read(game.join("Mods/SomeMod.dll")).unwrap(),
+            b"mod"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn removes_the_partly_moved_loader_when_switching_fails() {
+        let (root, game) = game_folder("removes_the_partly_moved_loader_when_switching_fails");
+        // An old loader without version.dll, so the new one has nothing to be moved back over
+        install_old_loader(&game);
+        std::fs::remove_file(game.join("version.dll")).unwrap();
+        let (archive, hash) = write_archive(&root, &RELEASE);
+        let staged = stage_loader(&Client::new(), &game, &archive, &hash)
+            .await
+            .unwrap();
+
+        // Fails at `dobby.dll` after the new `version.dll` was moved in, see above
+        std::fs::remove_file(staged.staging.join("dobby.dll")).unwrap();
+        std::fs::create_dir(staged.staging.join("dobby.dll")).unwrap();
+        std::os::unix::fs::symlink(root.join("nowhere"), game.join("dobby.dll")).unwrap();
+
+        let result = switch_loader(&game, staged).await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            entries(&game),
+            ["MelonLoader", "Mods", "Plugins", "UserData", "dobby.dll"]
+        );
+        assert_eq!(
+            std::fs::read(game.join("MelonLoader/MelonLoader.dll")).unwrap(),
+            b"old loader"
+        );
+        assert_eq!(
+            entries(&game.join("MelonLoader")),
+            ["Latest.log", "Loader.cfg", "Logs", "MelonLoader.dll"]
+        );
+    }
+
+    #[tokio::test]
+    async fn removes_the_previous_folder_when_moving_the_old_loader_fails() {
+        let (root, game) =
+            game_folder("removes_the_previous_folder_when_moving_the_old_loader_fails");
+        install_old_loader(&game);
+        let (archive, hash) = write_archive(&root, &RELEASE);
+        let staged = stage_loader(&Client::new(), &game, &archive, &hash)
+            .await
+            .unwrap();
+
+        // `MelonLoader` cannot be moved into itself, so moving aside fails after `version.dll`
+        let previous = game.join("MelonLoader").join(PREVIOUS_FOLDER_NAME);
+        let result = swap_loader(&game, &staged, &previous, true).await;
+        staged.discard().await;
+
+        assert!(result.is_err());
+        assert!(!previous.exists());
+        assert_eq!(
+            entries(&game),
+            ["MelonLoader", "Mods", "Plugins", "UserData", "version.dll"]
+        );
+        assert_eq!(
+            std::fs::read(game.join("version.dll")).unwrap(),
+            b"old proxy"
+        );
+    }
+}
@@ -6,29 +6,50 @@
 #![warn(clippy::suspicious)]
 #![allow(dead_code)]
 
+use clap::Parser;
+use std::process::ExitCode;
+
 pub(crate) mod api;
+pub(crate) mod assembly;
 pub mod authors;
+pub(crate) mod bisect;
 pub mod categories;
+pub(crate) mod cli;
 pub mod config;
+pub(crate) mod dependencies;
+pub(crate) mod disabler;
+pub(crate) mod game;
+pub(crate) mod health;
+pub(crate) mod installer;
+pub(crate) mod loader;
+pub(crate) mod loader_installer;
+pub(crate) mod manifest;
+pub(crate) mod melon_log;
+pub(crate) mod outdated;
+pub(crate) mod placement;
 pub mod promotions;
+pub(crate) mod scanner;
 pub(crate) mod sha256_hasher;
+pub(crate) mod uninstaller;
 pub mod utils;
 
-#[tokio::main]
-async fn main() {
-    let mods = api::fetch_all_mods().await.unwrap();
+fn main() -> ExitCode {
+    let cli = cli::Cli::parse();
 
-    for m in &mods {
-        let _ = api::download_and_verify_mod_with_info(m, "F:\\New folder").await;
-        println!("{} {}", m.name, m.id);
-    }
+    let result = tokio::runtime::Runtime::new()
+        .map_err(api::api_error::ApiError::from)
+        .and_then(|runtime| {
+            let result = runtime.block_on(cli::run(cli));
+            // A prompt interrupted by Ctrl-C is still blocked on stdin, do not wait for it
+            runtime.shutdown_background();
+            result
+        });
 
-    /*let _ = api::download_and_verify_mod(
-        "https://api.cvrmg.com/v1/mods/download/1",
-        "6iJpW/dHpcwDFwrDDwBrlKobNHnTVckNlLceHbhwst4=",
-        &ModType::Mod,
-        Path::new("F:\\New folder"),
-    )
-    .await
-    .ok();*/
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::from(err.exit_code())
+        }
+    }
 }
@@ -0,0 +1,268 @@
+use crate::api::{
+    api_error::ApiError,
+    mod_info::{self, ModInfo},
+    mod_version::{ApprovalStatus, ModType, ModVersion},
+};
+use crate::{disabler, utils};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+const MANIFEST_FILE_NAME: &str = "CVRModManager.lock.json";
+
+/// A mod that was installed by the manager.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InstalledMod {
+    pub(crate) id: usize,
+    pub(crate) name: String,
+    pub(crate) mod_version: Version,
+    pub(crate) mod_type: ModType,
+    pub(crate) hash: String,
+    /// Location of the DLL relative to the ChilloutVR folder.
+    pub(crate) file: PathBuf,
+    /// Approval status of the installed version at install time, `None` for entries
+    /// written before it was recorded.
+    #[serde(default)]
+    pub(crate) approval_status: Option<ApprovalStatus>,
+}
+
+impl InstalledMod {
+    pub(crate) fn new(
+        mod_info: &ModInfo,
+        mod_version: &ModVersion,
+        file: &Path,
+        chillout_folder: &Path,
+    ) -> Self {
+        let file = file.strip_prefix(chillout_folder).unwrap_or(file);
+
+        Self {
+            id: mod_info.id,
+            name: mod_info.name.clone(),
+            mod_version: mod_version.mod_version.clone(),
+            mod_type: mod_version.mod_type.clone(),
+            hash: mod_version.hash.clone(),
+            file: file.to_path_buf(),
+            approval_status: Some(mod_version.approval_status.clone()),
+        }
+    }
+
+    pub(crate) fn path(&self, chillout_folder: &Path) -> PathBuf {
+        chillout_folder.join(&self.file)
+    }
+
+    /// Whether the DLL was moved aside into a `Disabled` folder.
+    pub(crate) fn is_disabled(&self) -> bool {
+        disabler::is_disabled_path(&self.file)
+    }
+}
+
+/// The set of installed mods, stored in `UserData/CVRModManager.lock.json`.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct Manifest {
+    mods: BTreeMap<usize, InstalledMod>,
+}
+
+pub(crate) fn manifest_path(chillout_folder: &Path) -> PathBuf {
+    chillout_folder.join("UserData").join(MANIFEST_FILE_NAME)
+}
+
+impl Manifest {
+    /// Reads the manifest of `chillout_folder`, an empty one if it does not exist yet.
+    pub(crate) async fn load(chillout_folder: &Path) -> Result<Self, ApiError> {
+        let path = manifest_path(chillout_folder);
+        if !path.try_exists()? {
+            return Ok(Self::default());
+        }
+
+        let contents = tokio::fs::read_to_string(path).await?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Writes the manifest atomically, so a crash never leaves a truncated manifest behind.
+    pub(crate) async fn save(&self, chillout_folder: &Path) -> Result<(), ApiError> {
+        let contents = serde_json::to_string_pretty(self)?;
+        utils::write_file_atomic(&manifest_path(chillout_folder), contents.as_bytes()).await
+    }
+
+    /// Installed mods keyed by `ModInfo::id`.
+    pub(crate) fn installed(&self) -> &BTreeMap<usize, InstalledMod> {
+        &self.mods
+    }
+
+    pub(crate) fn get(&self, id: usize) -> Option<&InstalledMod> {
+        self.mods.get(&id)
+    }
+
+    pub(crate) fn insert(&mut self, installed: InstalledMod) -> Option<InstalledMod> {
+        self.mods.insert(installed.id, installed)
+    }
+
+    pub(crate) fn remove(&mut self, id: usize) -> Option<InstalledMod> {
+        self.mods.remove(&id)
+    }
+
+    /// Points every entry for the file at `from` to `to`, returning whether any changed.
+    pub(crate) fn relocate(&mut self, from: &Path, to: &Path, chillout_folder: &Path) -> bool {
+        let from = from.strip_prefix(chillout_folder).unwrap_or(from);
+        let to = to.strip_prefix(chillout_folder).unwrap_or(to);
+        let mut changed = false;
+
+        for installed in self
+            .mods
+            .values_mut()
+            .filter(|installed| installed.file == from)
+        {
+            installed.file = to.to_path_buf();
+            changed = true;
+        }
+
+        changed
+    }
+}
+
+/// Looks `query` up in the catalog first, then by id or name among installed mods,
+/// which covers mods that were removed from the API.
+pub(crate) fn find_installed<'a>(
+    mods: &[ModInfo],
+    installed: &'a [InstalledMod],
+    query: &str,
+) -> Option<&'a InstalledMod> {
+    if let Some(mod_info) = mod_info::find_mod(mods, query) {
+        if let Some(found) = installed
+            .iter()
+            .find(|installed| installed.id == mod_info.id)
+        {
+            return Some(found);
+        }
+    }
+
+    installed.iter().find(|installed| {
+        query
+            .trim()
+            .parse::<usize>()
+            .is_ok_and(|id| id == installed.id)
+            || installed.name.eq_ignore_ascii_case(query.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_installed, manifest_path, InstalledMod, Manifest};
+    use crate::api::mod_info::test_mod;
+    use crate::api::mod_version::ApprovalStatus;
+    use crate::utils;
+    use std::path::Path;
+
+    fn installed(id: usize, name: &str, file: &str) -> InstalledMod {
+        let mod_info = test_mod(id, name, "1.2.0", &[]);
+        InstalledMod::new(
+            &mod_info,
+            &mod_info.versions[0],
+            Path::new(file),
+            Path::new(""),
+        )
+    }
+
+    #[tokio::test]
+    async fn round_trips_through_the_lockfile() {
+        let game = utils::test_folder("round_trips_through_the_lockfile");
+        assert!(Manifest::load(&game).await.unwrap().installed().is_empty());
+
+        let mut manifest = Manifest::default();
+        manifest.insert(installed(113, "BTKUILib", "Mods/BTKUILib.dll"));
+        let mut outdated = installed(90, "UI Expansion Kit", "Mods/Disabled/UIExpansionKit.dll");
+        outdated.approval_status = Some(ApprovalStatus::Outdated(Some("2025r180".to_string())));
+        manifest.insert(outdated);
+        manifest.save(&game).await.unwrap();
+
+        assert!(manifest_path(&game).starts_with(game.join("UserData")));
+        let loaded = Manifest::load(&game).await.unwrap();
+        assert_eq!(loaded.installed(), manifest.installed());
+        assert!(loaded.get(90).unwrap().is_disabled());
+
+        // Entries written before the approval status was recorded still load
+        std::fs::write(
+            manifest_path(&game),
+            r#"{"mods": {"113": {"id": 113, "name": "BTKUILib", "modVersion": "1.2.0",
+                "modType": "Mod", "hash": "abc", "file": "Mods/BTKUILib.dll"}}}"#,
+        )
+        .unwrap();
+        let loaded = Manifest::load(&game).await.unwrap();
+        assert_eq!(loaded.get(113).unwrap().approval_status, None);
+        assert_eq!(loaded.get(113).unwrap().hash, "abc");
+    }
+
+    #[test]
+    fn finds_installed_mods() {
+        let mut ui = test_mod(113, "BTKUILib", "1.2.0", &[]);
+        ui.aliases = Some(vec!["BTK UI".to_string()]);
+        let mods = [ui, test_mod(90, "UI Expansion Kit", "1.0.0", &[])];
+        let installed = [
+            installed(113, "BTKUILib", "Mods/BTKUILib.dll"),
+            installed(7, "Removed Mod", "Mods/RemovedMod.dll"),
+        ];
+
+        // (query, installed id)
+        let cases: [(&str, Option<usize>); 8] = [
+            ("BTKUILib", Some(113)),
+            ("btk ui", Some(113)),
+            ("113", Some(113)),
+            // Mods that left the catalog are still found by id and name
+            ("7", Some(7)),
+            ("removed mod", Some(7)),
+            // In the catalog but not installed
+            ("UI Expansion Kit", None),
+            ("90", None),
+            ("Unknown", None),
+        ];
+
+        for (query, id) in cases {
+            assert_eq!(
+                find_installed(&mods, &installed, query).map(|found| found.id),
+                id,
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn relocates_entries() {
+        let game = Path::new("/games/ChilloutVR");
+        let mut manifest = Manifest::default();
+        manifest.insert(installed(113, "BTKUILib", "Mods/BTKUILib.dll"));
+        manifest.insert(installed(90, "UI Expansion Kit", "Mods/UIExpansionKit.dll"));
+
+        assert!(manifest.relocate(
+            &game.join("Mods/BTKUILib.dll"),
+            &game.join("Mods/Disabled/BTKUILib.dll"),
+            game,
+        ));
+        assert!(manifest.get(113).unwrap().is_disabled());
+        assert_eq!(
+            manifest.get(113).unwrap().path(game),
+            game.join("Mods/Disabled/BTKUILib.dll")
+        );
+
+        // Paths relative to the game folder work too
+        assert!(manifest.relocate(
+            Path::new("Mods/UIExpansionKit.dll"),
+            Path::new("Plugins/UIExpansionKit.dll"),
+            game,
+        ));
+        assert_eq!(
+            manifest.get(90).unwrap().file,
+            Path::new("Plugins/UIExpansionKit.dll")
+        );
+
+        assert!(!manifest.relocate(
+            &game.join("Mods/Unknown.dll"),
+            &game.join("Mods/Disabled/Unknown.dll"),
+            game,
+        ));
+    }
+}
@@ -0,0 +1,65 @@
+use regex::Regex;
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+// "[12:34:56.789] Game Version: 2024r176", the timestamp is missing in some versions
+static HEADER_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:\[[^\]]*\]\s*)?(?P<key>[A-Za-z][A-Za-z ]*?)\s*:\s*(?P<value>.*?)\s*$")
+        .expect("Invalid log header regex")
+});
+
+/// Lines at the top of the log that make up the header, the rest is mod output.
+const HEADER_LINES: usize = 64;
+
+/// Log written by the loader on the last game start.
+pub(crate) fn latest_log_path(chillout_folder: &Path) -> PathBuf {
+    chillout_folder.join("MelonLoader").join("Latest.log")
+}
+
+/// Reads the log of the last game start, `None` if the game never ran with the loader.
+pub(crate) async fn read_latest_log(chillout_folder: &Path) -> Option<String> {
+    let data = tokio::fs::read(latest_log_path(chillout_folder))
+        .await
+        .ok()?;
+    Some(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// Value of a `Key: Value` line in the log header, such as `Game Version`.
+pub(crate) fn header_value<'a>(log: &'a str, key: &str) -> Option<&'a str> {
+    log.lines().take(HEADER_LINES).find_map(|line| {
+        let captures = HEADER_LINE.captures(line)?;
+        let value = captures.name("value")?.as_str();
+        (captures["key"].eq_ignore_ascii_case(key) && !value.is_empty()).then_some(value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::header_value;
+
+    #[test]
+    fn reads_header_values() {
+        let log = "------------------------------\n\
+                   [12:00:00.000] MelonLoader v0.6.1 Open-Beta\n\
+                   [12:00:00.001] Game Name: ChilloutVR\n\
+                   [12:00:00.002] Game Version:   2024r176 ex1  \n\
+                   Unity Version: 2021.3.45f1\n\
+                   [12:00:00.003] Empty:\n";
+
+        assert_eq!(header_value(log, "Game Name"), Some("ChilloutVR"));
+        assert_eq!(header_value(log, "game version"), Some("2024r176 ex1"));
+        assert_eq!(header_value(log, "Unity Version"), Some("2021.3.45f1"));
+        assert_eq!(header_value(log, "Empty"), None);
+        assert_eq!(header_value(log, "Missing"), None);
+    }
+
+    #[test]
+    fn ignores_lines_after_the_header() {
+        let mut log = "[12:00:00.000] Game Name: ChilloutVR\n".repeat(64);
+        log.push_str("[12:00:01.000] Game Version: 2024r176\n");
+
+        assert_eq!(header_value(&log, "Game Version"), None);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::api::{api_error::ApiError, mod_info::ModInfo, mod_version::ApprovalStatus};
+use crate::manifest::{InstalledMod, Manifest};
+use crate::scanner::{self, ScannedFile};
+use crate::sha256_hasher;
+use semver::Version;
+use std::{fmt, path::Path};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum OutdatedState {
+    UpToDate,
+    /// The catalog has a newer version than the installed one.
+    UpdateAvailable,
+    /// Same version as the catalog, but the file on disk does not match its hash.
+    ModifiedLocally,
+    /// The installed file is gone.
+    FileMissing,
+    /// The mod no longer exists in the catalog.
+    RemovedFromApi,
+}
+
+impl fmt::Display for OutdatedState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::UpToDate => "up to date",
+            Self::UpdateAvailable => "newer version available",
+            Self::ModifiedLocally => "installed file modified locally",
+            Self::FileMissing => "installed file missing",
+            Self::RemovedFromApi => "removed from the API",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct OutdatedReport {
+    pub(crate) installed: InstalledMod,
+    pub(crate) available_version: Option<Version>,
+    pub(crate) approval_status: Option<ApprovalStatus>,
+    /// Whether the file on disk hashes to what the catalog lists for the installed version.
+    pub(crate) hash_matches: bool,
+    pub(crate) state: OutdatedState,
+}
+
+impl OutdatedReport {
+    /// Whether the file on disk differs from the version it was installed as, which can
+    /// come on top of a newer version being available.
+    pub(crate) fn is_modified_locally(&self) -> bool {
+        !self.hash_matches && self.state != OutdatedState::FileMissing
+    }
+
+    /// The state, followed by the local modification when the state does not already say so.
+    pub(crate) fn describe(&self) -> String {
+        if self.is_modified_locally() && self.state != OutdatedState::ModifiedLocally {
+            format!("{}, {}", self.state, OutdatedState::ModifiedLocally)
+        } else {
+            self.state.to_string()
+        }
+    }
+}
+
+/// Installed mods from the manifest, plus catalog mods found in `Mods`/`Plugins` by hash
+/// that the manifest does not know about.
+pub(crate) async fn collect_installed(
+    chillout_folder: &Path,
+    mods: &[ModInfo],
+) -> Result<Vec<InstalledMod>, ApiError> {
+    let scanned = scanner::scan_mod_folders(chillout_folder, mods).await?;
+    collect_scanned(chillout_folder, mods, &scanned).await
+}
+
+/// Like `collect_installed`, for a scan of the mod folders that was already done.
+pub(crate) async fn collect_scanned(
+    chillout_folder: &Path,
+    mods: &[ModInfo],
+    scanned: &[ScannedFile],
+) -> Result<Vec<InstalledMod>, ApiError> {
+    let mut manifest = Manifest::load(chillout_folder).await?;
+    scanner::import_into_manifest(&mut manifest, scanned, mods, chillout_folder);
+
+    Ok(manifest.installed().values().cloned().collect())
+}
+
+/// Compares every installed mod with the latest version in `mods`.
+pub(crate) async fn check_outdated(
+    chillout_folder: &Path,
+    mods: &[ModInfo],
+) -> Result<Vec<OutdatedReport>, ApiError> {
+    let mut reports = Vec::new();
+
+    for installed in collect_installed(chillout_folder, mods).await? {
+        let mod_info = mods.iter().find(|mod_info| mod_info.id == installed.id);
+        let report = check_installed_mod(chillout_folder, installed, mod_info).await?;
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+async fn check_installed_mod(
+    chillout_folder: &Path,
+    installed: InstalledMod,
+    mod_info: Option<&ModInfo>,
+) -> Result<OutdatedReport, ApiError> {
+    let path = installed.path(chillout_folder);
+    let disk_hash = if path.try_exists()? {
+        Some(sha256_hasher::compute_sha256_hash_of_file(&path).await?)
+    } else {
+        None
+    };
+
+    Ok(classify(installed, mod_info, disk_hash.as_deref()))
+}
+
+/// Compares `installed` with its catalog entry, given the hash of the file on disk or `None`
+/// when the file is missing.
+fn classify(
+    installed: InstalledMod,
+    mod_info: Option<&ModInfo>,
+    disk_hash: Option<&str>,
+) -> OutdatedReport {
+    let Some(latest) = mod_info.and_then(ModInfo::latest_version) else {
+        return OutdatedReport {
+            hash_matches: disk_hash == Some(installed.hash.as_str()),
+            installed,
+            available_version: None,
+            approval_status: None,
+            state: OutdatedState::RemovedFromApi,
+        };
+    };
+
+    // Prefer the hash the catalog lists for the installed version, the manifest may predate it
+    let expected_hash = mod_info
+        .into_iter()
+        .flat_map(|mod_info| mod_info.versions.iter())
+        .find(|version| version.mod_version == installed.mod_version)
+        .map_or(installed.hash.as_str(), |version| version.hash.as_str());
+    let hash_matches = disk_hash == Some(expected_hash);
+
+    let state = if disk_hash.is_none() {
+        OutdatedState::FileMissing
+    } else if latest.mod_version > installed.mod_version {
+        OutdatedState::UpdateAvailable
+    } else if !hash_matches {
+        OutdatedState::ModifiedLocally
+    } else {
+        OutdatedState::UpToDate
+    };
+
+    OutdatedReport {
+        available_version: Some(latest.mod_version.clone()),
+        approval_status: Some(latest.approval_status.clone()),
+        hash_matches,
+        installed,
+        state,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, OutdatedState};
+    use crate::api::mod_info::{test_mod, ModInfo};
+    use crate::manifest::InstalledMod;
+    use semver::Version;
+    use std::path::Path;
+
+    /// (installed version, manifest hash, disk hash, in catalog, state, description)
+    type Case<'a> = (
+        &'a str,
+        &'a str,
+        Option<&'a str>,
+        bool,
+        OutdatedState,
+        &'a str,
+    );
+
+    /// `BTKUILib` with 2.0.0 as the latest version and 1.0.0 before it.
+    fn catalog_entry() -> ModInfo {
+        let mut mod_info = test_mod(113, "BTKUILib", "2.0.0", &[]);
+        mod_info.versions[0].hash = "hash-2.0.0".to_string();
+
+        let mut older = mod_info.versions[0].clone();
+        older.mod_version = Version::new(1, 0, 0);
+        older.hash = "hash-1.0.0".to_string();
+        mod_info.versions.push(older);
+
+        mod_info
+    }
+
+    #[test]
+    fn classifies_installed_mods() {
+        let mod_info = catalog_entry();
+
+        let cases: [Case; 9] = [
+            (
+                "2.0.0",
+                "hash-2.0.0",
+                Some("hash-2.0.0"),
+                true,
+                OutdatedState::UpToDate,
+                "up to date",
+            ),
+            // The catalog's hash wins over a stale one in the manifest
+            (
+                "2.0.0",
+                "stale",
+                Some("hash-2.0.0"),
+                true,
+                OutdatedState::UpToDate,
+                "up to date",
+            ),
+            (
+                "1.0.0",
+                "hash-1.0.0",
+                Some("hash-1.0.0"),
+                true,
+                OutdatedState::UpdateAvailable,
+                "newer version available",
+            ),
+            (
+                "2.0.0",
+                "hash-2.0.0",
+                Some("edited"),
+                true,
+                OutdatedState::ModifiedLocally,
+                "installed file modified locally",
+            ),
+            (
+                "1.0.0",
+                "hash-1.0.0",
+                Some("edited"),
+                true,
+                OutdatedState::UpdateAvailable,
+                "newer version available, installed file modified locally",
+            ),
+            (
+                "2.0.0",
+                "hash-2.0.0",
+                None,
+                true,
+                OutdatedState::FileMissing,
+                "installed file missing",
+            ),
+            (
+                "1.0.0",
+                "hash-1.0.0",
+                None,
+                true,
+                OutdatedState::FileMissing,
+                "installed file missing",
+            ),
+            (
+                "1.0.0",
+                "hash-1.0.0",
+                Some("hash-1.0.0"),
+                false,
+                OutdatedState::RemovedFromApi,
+                "removed from the API",
+            ),
+            (
+                "1.0.0",
+                "hash-1.0.0",
+                Some("edited"),
+                false,
+                OutdatedState::RemovedFromApi,
+                "removed from the API, installed file modified locally",
+            ),
+        ];
+
+        for (version, manifest_hash, disk_hash, in_catalog, state, description) in cases {
+            let mut installed = InstalledMod::new(
+                &mod_info,
+                &mod_info.versions[0],
+                Path::new("Mods/BTKUILib.dll"),
+                Path::new(""),
+            );
+            installed.mod_version = Version::parse(version).unwrap();
+            installed.hash = manifest_hash.to_string();
+
+            let report = classify(installed, in_catalog.then_some(&mod_info), disk_hash);
+            assert_eq!(report.state, state, "{description}");
+            assert_eq!(report.describe(), description);
+            assert_eq!(
+                report.available_version,
+                in_catalog.then(|| Version::new(2, 0, 0)),
+                "{description}"
+            );
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use crate::api::{api_error::ApiError, mod_info::ModInfo, mod_version::ModType};
+use crate::manifest::Manifest;
+use crate::scanner::{self, ScannedFile};
+use crate::utils;
+use std::path::{Path, PathBuf};
+
+/// A DLL sitting in `Mods` while it should be in `Plugins`, or the other way around.
+#[derive(Debug, Clone)]
+pub(crate) struct Misplaced {
+    pub(crate) path: PathBuf,
+    pub(crate) name: String,
+    pub(crate) expected: ModType,
+}
+
+impl Misplaced {
+    /// Where the file belongs, keeping its name.
+    pub(crate) fn destination(&self, chillout_folder: &Path) -> PathBuf {
+        let file_name = self.path.file_name().unwrap_or(self.path.as_os_str());
+        chillout_folder
+            .join(self.expected.folder_name())
+            .join(file_name)
+    }
+}
+
+/// Scans `Mods` and `Plugins` for enabled files whose location does not match their type.
+///
+/// The type comes from the catalog for known files, and from the class the assembly's
+/// `MelonInfo` points at for unknown ones.
+pub(crate) async fn find_misplaced(
+    chillout_folder: &Path,
+    mods: &[ModInfo],
+) -> Result<Vec<Misplaced>, ApiError> {
+    let scanned = scanner::scan_mod_folders(chillout_folder, mods).await?;
+
+    Ok(scanned
+        .iter()
+        // Disabled files go back to the folder of their type when they are enabled
+        .filter(|file| !file.disabled)
+        .filter_map(|file| {
+            let (name, expected) = expected_type(mods, file)?;
+            (expected != file.location).then(|| Misplaced {
+                path: file.path.clone(),
+                name,
+                expected,
+            })
+        })
+        .collect())
+}
+
+fn expected_type(mods: &[ModInfo], file: &ScannedFile) -> Option<(String, ModType)> {
+    // Duplicates are still a known version, only their classification hides it
+    if let Some((id, _)) = &file.matched {
+        let mod_info = mods.iter().find(|mod_info| mod_info.id == *id)?;
+        let version = mod_info
+            .versions
+            .iter()
+            .find(|version| version.hash == file.hash)?;
+        return Some((mod_info.name.clone(), version.mod_type.clone()));
+    }
+
+    let assembly = file.assembly.as_ref()?;
+    let name = assembly
+        .melon_info
+        .as_ref()
+        .map(|melon_info| melon_info.name.clone())
+        .or_else(|| assembly.assembly_name.clone())?;
+
+    Some((name, assembly.mod_type.clone()?))
+}
+
+/// Results of moving misplaced files into place.
+#[derive(Debug)]
+pub(crate) struct MoveReport<'a> {
+    /// One per file, in the order they were found.
+    pub(crate) outcomes: Vec<(&'a Misplaced, Result<PathBuf, ApiError>)>,
+    /// Why the new locations could not be recorded in the manifest, if they could not.
+    pub(crate) manifest_error: Option<ApiError>,
+}
+
+/// Moves every misplaced file into the folder matching its type and updates the manifest.
+///
+/// Files are never overwritten, a file whose destination already exists is reported as failed.
+pub(crate) async fn move_misplaced<'a>(
+    chillout_folder: &Path,
+    misplaced: &'a [Misplaced],
+) -> Result<MoveReport<'a>, ApiError> {
+    let mut manifest = Manifest::load(chillout_folder).await?;
+    let mut outcomes = Vec::with_capacity(misplaced.len());
+    let mut changed = false;
+
+    for file in misplaced {
+        let result = utils::move_file(&file.path, &file.destination(chillout_folder)).await;
+        if let Ok(destination) = &result {
+            changed |= manifest.relocate(&file.path, destination, chillout_folder);
+        }
+        outcomes.push((file, result));
+    }
+
+    let manifest_error = if changed {
+        manifest.save(chillout_folder).await.err()
+    } else {
+        None
+    };
+
+    Ok(MoveReport {
+        outcomes,
+        manifest_error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_misplaced, move_misplaced};
+    use crate::api::{
+        api_error::ApiError,
+        mod_info::{test_mod, ModInfo},
+        mod_version::ModType,
+    };
+    use crate::assembly::test_assembly::{build_assembly, TestAssembly};
+    use crate::manifest::{InstalledMod, Manifest};
+    use crate::{sha256_hasher, utils};
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use tokio_util::bytes::Bytes;
+
+    /// A catalog mod of `mod_type` whose only version hashes to `data`.
+    fn catalog_mod(id: usize, name: &str, mod_type: ModType, data: &[u8]) -> ModInfo {
+        let mut mod_info = test_mod(id, name, "1.0.0", &[]);
+        mod_info.versions[0].mod_type = mod_type;
+        mod_info.versions[0].hash = sha256_hasher::compute_sha256_hash(&Bytes::from(data.to_vec()));
+        mod_info
+    }
+
+    fn write(game: &Path, path: &str, data: &[u8]) {
+        let path = game.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+    }
+
+    #[tokio::test]
+    async fn moves_misplaced_mods_and_plugins() {
+        let game = utils::test_folder("moves_misplaced_mods_and_plugins");
+        // A catalog plugin in Mods, an unknown mod in Plugins and a catalog mod in Plugins
+        // whose place in Mods is taken
+        let unknown_mod = build_assembly(&TestAssembly::default());
+        write(&game, "Mods/OpenVRFSR.dll", b"plugin");
+        write(&game, "Plugins/TestMod.dll", &unknown_mod);
+        write(&game, "Plugins/Taken.dll", b"taken");
+        write(&game, "Mods/Taken.dll", b"something else");
+        write(&game, "Mods/InPlace.dll", b"in place");
+        let mods = [
+            catalog_mod(6, "ML_OpenVR_FSR", ModType::Plugin, b"plugin"),
+            catalog_mod(7, "Taken", ModType::Mod, b"taken"),
+            catalog_mod(8, "In Place", ModType::Mod, b"in place"),
+        ];
+
+        let mut manifest = Manifest::default();
+        manifest.insert(InstalledMod::new(
+            &mods[0],
+            &mods[0].versions[0],
+            Path::new("Mods/OpenVRFSR.dll"),
+            &game,
+        ));
+        manifest.save(&game).await.unwrap();
+
+        let misplaced = find_misplaced(&game, &mods).await.unwrap();
+        let found: Vec<(&str, PathBuf, ModType)> = misplaced
+            .iter()
+            .map(|file| (file.name.as_str(), file.path.clone(), file.expected.clone()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "ML_OpenVR_FSR",
+                    game.join("Mods/OpenVRFSR.dll"),
+                    ModType::Plugin
+                ),
+                ("Taken", game.join("Plugins/Taken.dll"), ModType::Mod),
+                ("Test Mod", game.join("Plugins/TestMod.dll"), ModType::Mod),
+            ]
+        );
+
+        let report = move_misplaced(&game, &misplaced).await.unwrap();
+        assert!(report.manifest_error.is_none());
+        let results: Vec<(&str, Result<PathBuf, io::ErrorKind>)> = report
+            .outcomes
+            .into_iter()
+            .map(|(file, result)| {
+                let result = result.map_err(|err| match err {
+                    ApiError::IOError(err) => err.kind(),
+                    other => panic!("expected an IO error, got {other:?}"),
+                });
+                (file.name.as_str(), result)
+            })
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("ML_OpenVR_FSR", Ok(game.join("Plugins/OpenVRFSR.dll"))),
+                ("Taken", Err(io::ErrorKind::AlreadyExists)),
+                ("Test Mod", Ok(game.join("Mods/TestMod.dll"))),
+            ]
+        );
+
+        // Nothing is overwritten, and the manifest follows the moved plugin
+        assert_eq!(
+            std::fs::read(game.join("Mods/Taken.dll")).unwrap(),
+            b"something else"
+        );
+        assert_eq!(
+            std::fs::read(game.join("Plugins/Taken.dll")).unwrap(),
+            b"taken"
+        );
+        assert_eq!(
+            std::fs::read(game.join("Mods/TestMod.dll")).unwrap(),
+            unknown_mod
+        );
+        assert!(!game.join("Mods/OpenVRFSR.dll").exists());
+        assert_eq!(
+            Manifest::load(&game).await.unwrap().get(6).unwrap().file,
+            Path::new("Plugins/OpenVRFSR.dll")
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_the_moves_when_the_manifest_fails() {
+        let game = utils::test_folder("reports_the_moves_when_the_manifest_fails");
+        write(&game, "Mods/OpenVRFSR.dll", b"plugin");
+        let mods = [catalog_mod(6, "ML_OpenVR_FSR", ModType::Plugin, b"plugin")];
+        let mut manifest = Manifest::default();
+        manifest.insert(InstalledMod::new(
+            &mods[0],
+            &mods[0].versions[0],
+            Path::new("Mods/OpenVRFSR.dll"),
+            &game,
+        ));
+        manifest.save(&game).await.unwrap();
+        // The manifest is written through a temporary file, a folder in its place fails the save
+        std::fs::create_dir_all(game.join("UserData/CVRModManager.lock.json.tmp")).unwrap();
+
+        let misplaced = find_misplaced(&game, &mods).await.unwrap();
+        let report = move_misplaced(&game, &misplaced).await.unwrap();
+        assert!(report.manifest_error.is_some());
+        let moved: Vec<PathBuf> = report
+            .outcomes
+            .into_iter()
+            .map(|(_, result)| result.unwrap())
+            .collect();
+        assert_eq!(moved, [game.join("Plugins/OpenVRFSR.dll")]);
+        assert!(game.join("Plugins/OpenVRFSR.dll").exists());
+    }
+}
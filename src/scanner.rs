@@ -0,0 +1,357 @@
+use crate::api::{
+    api_error::ApiError,
+    mod_info::ModInfo,
+    mod_version::{ModType, ModVersion},
+};
+use crate::assembly::{self, AssemblyInfo};
+use crate::manifest::{InstalledMod, Manifest};
+use crate::{disabler, utils};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ScanClass {
+    /// Matches the latest version in the catalog.
+    KnownCurrent,
+    /// Matches an older version in the catalog.
+    KnownOutdated,
+    /// Matches nothing in the catalog.
+    Unknown,
+    /// Another copy of a mod that was already found in `of`.
+    Duplicate { of: PathBuf },
+}
+
+impl fmt::Display for ScanClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KnownCurrent => f.pad("current"),
+            Self::KnownOutdated => f.pad("outdated"),
+            Self::Unknown => f.pad("unknown"),
+            Self::Duplicate { of } => {
+                let name = of.file_name().map_or(of.as_os_str(), |name| name);
+                f.pad(&format!("duplicate of {}", name.to_string_lossy()))
+            }
+        }
+    }
+}
+
+/// A DLL found in `Mods` or `Plugins`, or in their `Disabled` folders.
+#[derive(Debug, Clone)]
+pub(crate) struct ScannedFile {
+    pub(crate) path: PathBuf,
+    /// Base64 SHA-256, the same format as `ModVersion::hash`.
+    pub(crate) hash: String,
+    /// The folder the file was found in.
+    pub(crate) location: ModType,
+    /// Whether the file is in the `Disabled` folder of its location.
+    pub(crate) disabled: bool,
+    /// Catalog id and version the hash belongs to.
+    pub(crate) matched: Option<(usize, semver::Version)>,
+    pub(crate) class: ScanClass,
+    /// Assembly metadata of an unknown file, if it is a .NET assembly.
+    pub(crate) assembly: Option<AssemblyInfo>,
+    /// Catalog id, and version if one matched, guessed from the assembly metadata.
+    pub(crate) guessed: Option<(usize, Option<semver::Version>)>,
+}
+
+/// Hashes every DLL in `Mods` and `Plugins` and their `Disabled` folders and matches it
+/// against the catalog.
+///
+/// Enabled files are scanned first, so a disabled copy of an enabled mod is the duplicate.
+pub(crate) async fn scan_mod_folders(
+    chillout_folder: &Path,
+    mods: &[ModInfo],
+) -> Result<Vec<ScannedFile>, ApiError> {
+    let by_hash: HashMap<&str, (&ModInfo, &ModVersion)> = mods
+        .iter()
+        .flat_map(|mod_info| {
+            mod_info
+                .versions
+                .iter()
+                .map(move |version| (version.hash.as_str(), (mod_info, version)))
+        })
+        .collect();
+
+    let mut scanned = Vec::new();
+    let mut first_copy: HashMap<usize, PathBuf> = HashMap::new();
+
+    let folders = [false, true]
+        .into_iter()
+        .flat_map(|disabled| [ModType::Mod, ModType::Plugin].map(|location| (location, disabled)));
+
+    for (location, disabled) in folders {
+        let mut folder = chillout_folder.join(location.folder_name());
+        if disabled {
+            folder.push(disabler::DISABLED_FOLDER_NAME);
+        }
+
+        for (path, hash) in utils::hash_dlls_in_folder(&folder).await? {
+            let (matched, class) = match by_hash.get(hash.as_str()) {
+                None => (None, ScanClass::Unknown),
+                Some((mod_info, version)) => {
+                    let class = if let Some(of) = first_copy.get(&mod_info.id) {
+                        ScanClass::Duplicate { of: of.clone() }
+                    } else if mod_info
+                        .latest_version()
+                        .is_some_and(|latest| latest.hash == version.hash)
+                    {
+                        ScanClass::KnownCurrent
+                    } else {
+                        ScanClass::KnownOutdated
+                    };
+
+                    first_copy
+                        .entry(mod_info.id)
+                        .or_insert_with(|| path.clone());
+                    (Some((mod_info.id, version.mod_version.clone())), class)
+                }
+            };
+
+            // Manually built or renamed mods, identify them by their MelonInfo instead
+            let (assembly, guessed) = if class == ScanClass::Unknown {
+                identify_assembly(&path, mods).await
+            } else {
+                (None, None)
+            };
+
+            scanned.push(ScannedFile {
+                path,
+                hash,
+                location: location.clone(),
+                disabled,
+                matched,
+                class,
+                assembly,
+                guessed,
+            });
+        }
+    }
+
+    Ok(scanned)
+}
+
+async fn identify_assembly(
+    path: &Path,
+    mods: &[ModInfo],
+) -> (
+    Option<AssemblyInfo>,
+    Option<(usize, Option<semver::Version>)>,
+) {
+    let Ok(info) = assembly::read_assembly_info_from_file(path).await else {
+        return (None, None);
+    };
+
+    let guessed = assembly::match_catalog(mods, &info).map(|(mod_info, version)| {
+        (
+            mod_info.id,
+            version.map(|version| version.mod_version.clone()),
+        )
+    });
+
+    (Some(info), guessed)
+}
+
+/// Catalog mod and version of a scanned file, unless it is unknown or a duplicate.
+pub(crate) fn scanned_version<'a>(
+    mods: &'a [ModInfo],
+    file: &ScannedFile,
+) -> Option<(&'a ModInfo, &'a ModVersion)> {
+    if matches!(file.class, ScanClass::Unknown | ScanClass::Duplicate { .. }) {
+        return None;
+    }
+
+    let (id, _) = file.matched.as_ref()?;
+    let mod_info = mods.iter().find(|mod_info| mod_info.id == *id)?;
+    let version = mod_info
+        .versions
+        .iter()
+        .find(|version| version.hash == file.hash)?;
+
+    Some((mod_info, version))
+}
+
+/// Adds every identified file the manifest does not track yet, returning the new entries.
+///
+/// Files found in a `Disabled` folder are recorded as disabled.
+pub(crate) fn import_into_manifest(
+    manifest: &mut Manifest,
+    scanned: &[ScannedFile],
+    mods: &[ModInfo],
+    chillout_folder: &Path,
+) -> Vec<InstalledMod> {
+    let mut imported = Vec::new();
+
+    for file in scanned {
+        let Some((mod_info, version)) = scanned_version(mods, file) else {
+            continue;
+        };
+        if manifest.get(mod_info.id).is_some() {
+            continue;
+        }
+
+        let mut installed = InstalledMod::new(mod_info, version, &file.path, chillout_folder);
+        // Installed at some unknown point, the catalog's current status says nothing about then
+        installed.approval_status = None;
+        manifest.insert(installed.clone());
+        imported.push(installed);
+    }
+
+    imported
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{import_into_manifest, scan_mod_folders, ScanClass};
+    use crate::api::mod_info::{test_mod, ModInfo};
+    use crate::assembly::test_assembly::{build_assembly, TestAssembly};
+    use crate::manifest::{InstalledMod, Manifest};
+    use crate::{sha256_hasher, utils};
+    use semver::Version;
+    use std::path::{Path, PathBuf};
+    use tokio_util::bytes::Bytes;
+
+    fn hash(data: &[u8]) -> String {
+        sha256_hasher::compute_sha256_hash(&Bytes::from(data.to_vec()))
+    }
+
+    fn write(game: &Path, path: &str, data: &[u8]) {
+        let path = game.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+    }
+
+    fn assembly(assembly_name: &str, name: &str) -> Vec<u8> {
+        build_assembly(&TestAssembly {
+            assembly_name,
+            name,
+            ..TestAssembly::default()
+        })
+    }
+
+    /// A current mod, an outdated one, and one whose hash is in no version.
+    fn catalog(current: &[u8], outdated: &[u8]) -> Vec<ModInfo> {
+        let mut current_mod = test_mod(1, "Current Mod", "1.0.0", &[]);
+        current_mod.versions[0].hash = hash(current);
+
+        let mut outdated_mod = test_mod(2, "Older Mod", "2.0.0", &[]);
+        let mut old_version = outdated_mod.versions[0].clone();
+        old_version.mod_version = Version::new(1, 0, 0);
+        old_version.hash = hash(outdated);
+        outdated_mod.versions.push(old_version);
+
+        vec![
+            current_mod,
+            outdated_mod,
+            test_mod(3, "Renamed Mod", "1.0.0", &[]),
+        ]
+    }
+
+    #[tokio::test]
+    async fn classifies_scanned_files() {
+        let game = utils::test_folder("classifies_scanned_files");
+        let current = assembly("Current", "Current Mod");
+        let outdated = assembly("Older", "Older Mod");
+        write(&game, "Mods/Current.dll", &current);
+        write(
+            &game,
+            "Mods/Renamed.dll",
+            &assembly("Renamed", "Renamed Mod"),
+        );
+        write(&game, "Plugins/Unrelated.dll", b"not an assembly");
+        write(&game, "Mods/Disabled/CurrentCopy.dll", &current);
+        write(&game, "Plugins/Disabled/Older.dll", &outdated);
+        let mods = catalog(&current, &outdated);
+
+        let scanned = scan_mod_folders(&game, &mods).await.unwrap();
+        let found: Vec<(PathBuf, ScanClass, bool)> = scanned
+            .iter()
+            .map(|file| (file.path.clone(), file.class.clone(), file.disabled))
+            .collect();
+        // Enabled files come first, so the disabled copy is the duplicate
+        assert_eq!(
+            found,
+            [
+                (
+                    game.join("Mods/Current.dll"),
+                    ScanClass::KnownCurrent,
+                    false
+                ),
+                (game.join("Mods/Renamed.dll"), ScanClass::Unknown, false),
+                (
+                    game.join("Plugins/Unrelated.dll"),
+                    ScanClass::Unknown,
+                    false
+                ),
+                (
+                    game.join("Mods/Disabled/CurrentCopy.dll"),
+                    ScanClass::Duplicate {
+                        of: game.join("Mods/Current.dll")
+                    },
+                    true
+                ),
+                (
+                    game.join("Plugins/Disabled/Older.dll"),
+                    ScanClass::KnownOutdated,
+                    true
+                ),
+            ]
+        );
+
+        let matched: Vec<Option<(usize, Version)>> =
+            scanned.iter().map(|file| file.matched.clone()).collect();
+        assert_eq!(
+            matched,
+            [
+                Some((1, Version::new(1, 0, 0))),
+                None,
+                None,
+                Some((1, Version::new(1, 0, 0))),
+                Some((2, Version::new(1, 0, 0))),
+            ]
+        );
+
+        // Unknown files are identified by their MelonInfo when they are assemblies
+        assert_eq!(scanned[1].guessed, Some((3, Some(Version::new(1, 0, 0)))));
+        assert!(scanned[1].assembly.is_some());
+        assert_eq!(scanned[2].guessed, None);
+        assert!(scanned[2].assembly.is_none());
+    }
+
+    #[tokio::test]
+    async fn import_keeps_existing_entries() {
+        let game = utils::test_folder("import_keeps_existing_entries");
+        let current = assembly("Current", "Current Mod");
+        let outdated = assembly("Older", "Older Mod");
+        write(&game, "Mods/Current.dll", &current);
+        write(&game, "Mods/Disabled/Older.dll", &outdated);
+        let mods = catalog(&current, &outdated);
+
+        // Already tracked under another file name
+        let mut manifest = Manifest::default();
+        let existing = InstalledMod::new(
+            &mods[0],
+            &mods[0].versions[0],
+            Path::new("Mods/CurrentMod.dll"),
+            &game,
+        );
+        manifest.insert(existing.clone());
+
+        let scanned = scan_mod_folders(&game, &mods).await.unwrap();
+        let imported = import_into_manifest(&mut manifest, &scanned, &mods, &game);
+
+        assert_eq!(imported.len(), 1);
+        let older = manifest.get(2).unwrap();
+        assert_eq!(older.file, Path::new("Mods/Disabled/Older.dll"));
+        assert_eq!(older.mod_version, Version::new(1, 0, 0));
+        assert_eq!(older.approval_status, None);
+        assert!(older.is_disabled());
+
+        let kept = manifest.get(1).unwrap();
+        assert_eq!(kept.file, existing.file);
+        assert_eq!(kept.approval_status, existing.approval_status);
+        assert_eq!(manifest.installed().len(), 2);
+    }
+}
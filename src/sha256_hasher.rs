@@ -1,10 +1,35 @@
 use base64::{engine::general_purpose, Engine as _};
 use sha2::Digest;
+use std::fmt::Write as _;
+use std::path::Path;
 use tokio_util::bytes::Bytes;
 
 pub(crate) fn compute_sha256_hash(data: &Bytes) -> String {
     let mut hasher = sha2::Sha256::new();
     hasher.update(data);
-    let result = hasher.finalize();
-    general_purpose::STANDARD.encode(result)
+    encode_hash(hasher)
+}
+
+/// Finishes an incrementally fed hasher into the base64 format the API uses.
+pub(crate) fn encode_hash(hasher: sha2::Sha256) -> String {
+    general_purpose::STANDARD.encode(hasher.finalize())
+}
+
+/// Whether `data` hashes to `expected`, given in base64 like the API's hashes or in hex like
+/// the checksums published with releases.
+pub(crate) fn matches_hash(data: &[u8], expected: &str) -> bool {
+    let expected = expected.trim();
+    let digest = sha2::Sha256::digest(data);
+    let hex = digest.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    });
+
+    expected.eq_ignore_ascii_case(&hex) || expected == general_purpose::STANDARD.encode(digest)
+}
+
+/// Hashes a file on disk in the same base64 format the API uses for `ModVersion::hash`.
+pub(crate) async fn compute_sha256_hash_of_file(path: &Path) -> std::io::Result<String> {
+    let data = tokio::fs::read(path).await?;
+    Ok(compute_sha256_hash(&Bytes::from(data)))
 }
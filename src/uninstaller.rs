@@ -0,0 +1,321 @@
+use crate::api::{api_error::ApiError, mod_info::ModInfo};
+use crate::dependencies;
+use crate::manifest::{self, InstalledMod, Manifest};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// What `uninstall_mod` is going to remove, worked out before anything is deleted.
+#[derive(Debug)]
+pub(crate) struct UninstallPlan {
+    pub(crate) target: InstalledMod,
+    pub(crate) files: Vec<PathBuf>,
+    /// Files and folders in `UserData` named after the mod, only filled when purging.
+    pub(crate) config: Vec<PathBuf>,
+    /// Installed mods that still require the target.
+    pub(crate) dependents: Vec<String>,
+}
+
+/// What `uninstall_mod` removed.
+#[derive(Debug, Default)]
+pub(crate) struct UninstallReport {
+    pub(crate) name: String,
+    pub(crate) removed_files: Vec<PathBuf>,
+    pub(crate) removed_config: Vec<PathBuf>,
+}
+
+/// Finds what removing the mod matching `query` (id, name or alias) involves.
+///
+/// Its DLL is found among `installed` and in `hashes`, the installed DLLs keyed by hash, so
+/// mods installed by hand are found too. With `purge_config` the entries directly inside
+/// `UserData` named after the mod, its DLL or an alias are listed as well.
+pub(crate) async fn plan_uninstall(
+    mods: &[ModInfo],
+    installed: &[InstalledMod],
+    hashes: &HashMap<String, Vec<PathBuf>>,
+    query: &str,
+    chillout_folder: &Path,
+    purge_config: bool,
+) -> Result<UninstallPlan, ApiError> {
+    let target = manifest::find_installed(mods, installed, query)
+        .ok_or_else(|| ApiError::ModNotInstalled(query.to_string()))?
+        .clone();
+    let catalog_entry = mods.iter().find(|mod_info| mod_info.id == target.id);
+
+    let mut files = vec![target.path(chillout_folder)];
+    if let Some(mod_info) = catalog_entry {
+        for version in &mod_info.versions {
+            files.extend(hashes.get(&version.hash).into_iter().flatten().cloned());
+        }
+    }
+    files.sort();
+    files.dedup();
+
+    let config = if purge_config {
+        let mut names = vec![target.name.clone()];
+        names.extend(
+            target
+                .file
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned()),
+        );
+        names.extend(
+            catalog_entry
+                .into_iter()
+                .flat_map(|mod_info| mod_info.aliases.iter().flatten().cloned()),
+        );
+        find_user_data(chillout_folder, &names).await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(UninstallPlan {
+        dependents: dependencies::installed_dependents(mods, installed, target.id)
+            .into_iter()
+            .map(|dependent| dependent.name.clone())
+            .collect(),
+        target,
+        files,
+        config,
+    })
+}
+
+/// Drops the dependents that are removed by `plans` as well, then fails for the first mod
+/// still required by another one unless `force` is set.
+pub(crate) fn check_dependents(plans: &mut [UninstallPlan], force: bool) -> Result<(), ApiError> {
+    let targets: Vec<String> = plans.iter().map(|plan| plan.target.name.clone()).collect();
+    for plan in plans.iter_mut() {
+        plan.dependents
+            .retain(|dependent| !targets.contains(dependent));
+    }
+
+    match plans.iter().find(|plan| !plan.dependents.is_empty()) {
+        Some(plan) if !force => Err(ApiError::RequiredByInstalled {
+            mod_name: plan.target.name.clone(),
+            dependents: plan.dependents.join(", "),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Removes what `plan` lists from `Mods`/`Plugins`, `UserData` and the manifest.
+pub(crate) async fn uninstall_mod(
+    chillout_folder: &Path,
+    plan: UninstallPlan,
+) -> Result<UninstallReport, ApiError> {
+    let mut report = UninstallReport {
+        name: plan.target.name.clone(),
+        ..UninstallReport::default()
+    };
+
+    for path in plan.files {
+        if path.try_exists()? {
+            tokio::fs::remove_file(&path).await?;
+            report.removed_files.push(path);
+        }
+    }
+
+    for path in plan.config {
+        if path.is_dir() {
+            tokio::fs::remove_dir_all(&path).await?;
+        } else if path.try_exists()? {
+            tokio::fs::remove_file(&path).await?;
+        } else {
+            continue;
+        }
+        report.removed_config.push(path);
+    }
+
+    let mut manifest = Manifest::load(chillout_folder).await?;
+    if manifest.remove(plan.target.id).is_some() {
+        manifest.save(chillout_folder).await?;
+    }
+
+    Ok(report)
+}
+
+/// Files and folders directly inside `UserData` whose name, without extension, is one
+/// of `names`.
+async fn find_user_data(
+    chillout_folder: &Path,
+    names: &[String],
+) -> Result<Vec<PathBuf>, ApiError> {
+    let user_data = chillout_folder.join("UserData");
+    if !user_data.try_exists()? {
+        return Ok(Vec::new());
+    }
+
+    let mut found = Vec::new();
+    let mut entries = tokio::fs::read_dir(&user_data).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let stem = if entry.file_type().await?.is_dir() {
+            path.file_name()
+        } else {
+            path.file_stem()
+        };
+
+        let matches = stem
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| names.iter().any(|name| name.eq_ignore_ascii_case(stem)));
+        if matches {
+            found.push(path);
+        }
+    }
+
+    found.sort();
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_dependents, find_user_data, plan_uninstall, uninstall_mod, UninstallPlan};
+    use crate::api::{api_error::ApiError, mod_info::test_mod, mod_info::ModInfo};
+    use crate::manifest::{InstalledMod, Manifest};
+    use crate::{outdated, sha256_hasher, utils};
+    use std::path::{Path, PathBuf};
+    use tokio_util::bytes::Bytes;
+
+    /// `BTKUILib` and `Blackout`, which requires it, both installed by hand into `Mods`.
+    fn install_mods(game: &Path) -> Vec<ModInfo> {
+        let mut ui = test_mod(113, "BTKUILib", "2.0.0", &[]);
+        let mut blackout = test_mod(
+            106,
+            "Blackout",
+            "1.0.0",
+            &["[BTKUILib](https://api.cvrmg.com/v1/mods/download/113)"],
+        );
+
+        std::fs::create_dir_all(game.join("Mods")).unwrap();
+        for (mod_info, file) in [(&mut ui, "BTKUILib.dll"), (&mut blackout, "Blackout.dll")] {
+            let data = Bytes::from(file.to_string());
+            std::fs::write(game.join("Mods").join(file), &data).unwrap();
+            mod_info.versions[0].hash = sha256_hasher::compute_sha256_hash(&data);
+        }
+
+        vec![ui, blackout]
+    }
+
+    fn write_user_data(game: &Path, entries: &[&str]) {
+        for entry in entries {
+            let path = game.join("UserData").join(entry);
+            if path.extension().is_some() {
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(path, b"config").unwrap();
+            } else {
+                std::fs::create_dir_all(path.join("Settings")).unwrap();
+            }
+        }
+    }
+
+    async fn plan(game: &Path, mods: &[ModInfo], query: &str) -> UninstallPlan {
+        let installed = outdated::collect_installed(game, mods).await.unwrap();
+        let hashes = utils::hash_installed_dlls(game).await.unwrap();
+        plan_uninstall(mods, &installed, &hashes, query, game, true)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn purges_only_config_named_after_the_mod() {
+        let game = utils::test_folder("purges_only_config_named_after_the_mod");
+        write_user_data(
+            &game,
+            &[
+                "BTKUILib",
+                "btkuilib.cfg",
+                "BTKUILib.json",
+                "BTKUILib2",
+                "BTKUILibExtras.cfg",
+                "NotBTKUILib",
+                "BTKUILib.cfg.bak",
+                "MelonPreferences.cfg",
+            ],
+        );
+
+        let found = find_user_data(&game, &["BTKUILib".to_string()])
+            .await
+            .unwrap();
+
+        let user_data = game.join("UserData");
+        assert_eq!(
+            found,
+            ["BTKUILib", "BTKUILib.json", "btkuilib.cfg"].map(|entry| user_data.join(entry))
+        );
+        // No UserData at all is nothing to purge
+        let empty = utils::test_folder("purges_only_config_named_after_the_mod-empty");
+        assert!(find_user_data(&empty, &["BTKUILib".to_string()])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn dependents_block_removal_unless_forced() {
+        let game = utils::test_folder("dependents_block_removal_unless_forced");
+        let mods = install_mods(&game);
+
+        let ui = plan(&game, &mods, "BTKUILib").await;
+        assert_eq!(ui.dependents, ["Blackout"]);
+        assert_eq!(ui.files, [game.join("Mods/BTKUILib.dll")]);
+
+        let mut plans = [ui];
+        match check_dependents(&mut plans, false) {
+            Err(ApiError::RequiredByInstalled {
+                mod_name,
+                dependents,
+            }) => {
+                assert_eq!(mod_name, "BTKUILib");
+                assert_eq!(dependents, "Blackout");
+            }
+            other => panic!("expected a refusal, got {other:?}"),
+        }
+        check_dependents(&mut plans, true).unwrap();
+        assert_eq!(plans[0].dependents, ["Blackout"]);
+
+        // Removing the dependent in the same command is fine
+        let mut plans = [
+            plan(&game, &mods, "BTKUILib").await,
+            plan(&game, &mods, "Blackout").await,
+        ];
+        check_dependents(&mut plans, false).unwrap();
+        assert!(plans.iter().all(|plan| plan.dependents.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn removes_files_config_and_manifest_entry() {
+        let game = utils::test_folder("removes_files_config_and_manifest_entry");
+        let mods = install_mods(&game);
+        write_user_data(&game, &["BTKUILib", "BTKUILib.cfg", "BTKUILibExtras.cfg"]);
+
+        let mut manifest = Manifest::default();
+        for (mod_info, file) in mods.iter().zip(["BTKUILib.dll", "Blackout.dll"]) {
+            manifest.insert(InstalledMod::new(
+                mod_info,
+                &mod_info.versions[0],
+                &PathBuf::from("Mods").join(file),
+                &game,
+            ));
+        }
+        manifest.save(&game).await.unwrap();
+
+        let plan = plan(&game, &mods, "113").await;
+        let report = uninstall_mod(&game, plan).await.unwrap();
+
+        assert_eq!(report.name, "BTKUILib");
+        assert_eq!(report.removed_files, [game.join("Mods/BTKUILib.dll")]);
+        assert_eq!(
+            report.removed_config,
+            ["BTKUILib", "BTKUILib.cfg"].map(|entry| game.join("UserData").join(entry))
+        );
+        assert!(!game.join("Mods/BTKUILib.dll").exists());
+        assert!(game.join("Mods/Blackout.dll").exists());
+        assert!(game.join("UserData/BTKUILibExtras.cfg").exists());
+
+        let manifest = Manifest::load(&game).await.unwrap();
+        assert!(manifest.get(113).is_none());
+        assert!(manifest.get(106).is_some());
+    }
+}
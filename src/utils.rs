@@ -1,6 +1,13 @@
-use crate::{api::api_error::ApiError, config};
-use std::path::Path;
-use tokio::fs::File;
+use crate::{
+    api::{api_error::ApiError, mod_version::ModType},
+    config, disabler, sha256_hasher,
+};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+use tokio::{fs::File, io::AsyncWriteExt};
 
 pub(crate) async fn create_file_with_directories(path: &Path) -> Result<File, ApiError> {
     // Ensure the parent directory exists
@@ -14,6 +21,41 @@ pub(crate) async fn create_file_with_directories(path: &Path) -> Result<File, Ap
     Ok(file)
 }
 
+/// Writes `contents` to a temporary file next to `path` and renames it into place,
+/// so readers never see a partially written file.
+pub(crate) async fn write_file_atomic(path: &Path, contents: &[u8]) -> Result<(), ApiError> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut file = create_file_with_directories(&temp_path).await?;
+    file.write_all(contents).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    tokio::fs::rename(&temp_path, path).await?;
+    Ok(())
+}
+
+/// Moves the file at `from` to `to`, creating the folder it goes into. Never overwrites,
+/// fails if `to` already exists.
+pub(crate) async fn move_file(from: &Path, to: &Path) -> Result<PathBuf, ApiError> {
+    if to.try_exists()? {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        )
+        .into());
+    }
+
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::rename(from, to).await?;
+
+    Ok(to.to_path_buf())
+}
+
 pub(crate) async fn get_all_files_in_directory(
     path: &Path,
     filter: &str,
@@ -37,9 +79,139 @@ pub(crate) async fn get_all_files_in_directory(
     Ok(files)
 }
 
+/// Hashes every DLL in the `Mods` and `Plugins` folders and their `Disabled` folders,
+/// keyed by the base64 SHA-256 hash.
+///
+/// Missing folders are treated as empty.
+pub(crate) async fn hash_installed_dlls(
+    chillout_folder: &Path,
+) -> Result<HashMap<String, Vec<PathBuf>>, ApiError> {
+    let mut hashes: HashMap<String, Vec<PathBuf>> = HashMap::new();
+
+    let folders = [ModType::Mod, ModType::Plugin]
+        .map(|mod_type| chillout_folder.join(mod_type.folder_name()));
+    let disabled_folders = folders
+        .clone()
+        .map(|folder| folder.join(disabler::DISABLED_FOLDER_NAME));
+
+    for folder in folders.into_iter().chain(disabled_folders) {
+        for (path, hash) in hash_dlls_in_folder(&folder).await? {
+            hashes.entry(hash).or_default().push(path);
+        }
+    }
+
+    Ok(hashes)
+}
+
+/// Hashes every DLL directly inside `folder`, sorted by path, with the base64 SHA-256 hash.
+///
+/// A missing folder is treated as empty.
+pub(crate) async fn hash_dlls_in_folder(folder: &Path) -> Result<Vec<(PathBuf, String)>, ApiError> {
+    if !folder.try_exists().unwrap_or(false) {
+        return Ok(Vec::new());
+    }
+
+    let mut file_names = get_all_files_in_directory(folder, "").await?;
+    file_names.sort();
+
+    let mut hashed = Vec::new();
+    for file_name in file_names {
+        let path = folder.join(file_name);
+        if !path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("dll"))
+        {
+            continue;
+        }
+
+        let hash = sha256_hasher::compute_sha256_hash_of_file(&path).await?;
+        hashed.push((path, hash));
+    }
+
+    Ok(hashed)
+}
+
+/// Asks a yes/no question on the terminal until it is answered with one of the two.
+///
+/// An empty answer or a closed stdin means `default`. Without a default the question is
+/// asked again, and a closed stdin is an error.
+pub(crate) async fn confirm(question: &str, default: Option<bool>) -> Result<bool, ApiError> {
+    let question = question.to_string();
+    let answer = tokio::task::spawn_blocking(move || {
+        read_answer(
+            &mut io::stdin().lock(),
+            &mut io::stdout(),
+            &question,
+            default,
+        )
+    })
+    .await??;
+
+    Ok(answer)
+}
+
+fn read_answer(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: Option<bool>,
+) -> io::Result<bool> {
+    let choices = match default {
+        Some(true) => "[Y/n]",
+        Some(false) => "[y/N]",
+        None => "[y/n]",
+    };
+
+    loop {
+        write!(output, "{question} {choices} ")?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return default.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "stdin closed before an answer",
+                )
+            });
+        }
+
+        match line.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            "" => {
+                if let Some(default) = default {
+                    return Ok(default);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The first error of a batch that carries on past failures.
+#[derive(Debug, Default)]
+pub(crate) struct FirstError(Option<ApiError>);
+
+impl FirstError {
+    /// Prints that `action` failed and keeps `err` if it is the first.
+    pub(crate) fn record(&mut self, action: &str, err: ApiError) {
+        eprintln!("Failed to {action}: {err}");
+        self.0.get_or_insert(err);
+    }
+
+    /// `Err` with the first recorded error, `Ok` if nothing failed.
+    pub(crate) fn into_result(self) -> Result<(), ApiError> {
+        self.0.map_or(Ok(()), Err)
+    }
+}
+
 pub fn is_melon_loader_installed() -> bool {
-    let chillout_folder_path = Path::new(config::CONFIGURATION_INSTANCE.chillout_folder());
+    is_melon_loader_installed_in(Path::new(config::CONFIGURATION_INSTANCE.chillout_folder()))
+}
 
+#[must_use]
+pub fn is_melon_loader_installed_in(chillout_folder_path: &Path) -> bool {
     let version_dll = chillout_folder_path
         .join("version.dll")
         .try_exists()
@@ -83,3 +255,69 @@ pub fn remove_melon_loader() -> Result<(), String> {
 
     Ok(())
 }
+
+/// Empty folder for a test, named after it so parallel tests do not share files.
+#[cfg(test)]
+pub(crate) fn test_folder(name: &str) -> PathBuf {
+    let folder = std::env::temp_dir()
+        .join("CVRModManager-tests")
+        .join(format!("{name}-{}", std::process::id()));
+    if folder.exists() {
+        std::fs::remove_dir_all(&folder).expect("Failed to clear the test folder");
+    }
+    std::fs::create_dir_all(&folder).expect("Failed to create the test folder");
+    folder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_answer, FirstError};
+    use crate::api::api_error::ApiError;
+    use std::io::{self, Cursor};
+
+    #[test]
+    fn reads_yes_or_no() {
+        // (typed, default, answer, questions asked)
+        let cases: [(&str, Option<bool>, Option<bool>, usize); 9] = [
+            ("y\n", None, Some(true), 1),
+            ("YES\n", Some(false), Some(true), 1),
+            (" n \n", Some(true), Some(false), 1),
+            ("\n", Some(false), Some(false), 1),
+            ("\n", Some(true), Some(true), 1),
+            ("", Some(false), Some(false), 1),
+            ("maybe\n\nno\n", None, Some(false), 3),
+            ("maybe\ny\n", Some(false), Some(true), 2),
+            // Nothing to fall back on once stdin is closed
+            ("maybe\n", None, None, 2),
+        ];
+
+        for (typed, default, answer, asked) in cases {
+            let mut output = Vec::new();
+            let result = read_answer(&mut Cursor::new(typed), &mut output, "Go?", default);
+
+            match answer {
+                Some(answer) => assert_eq!(result.unwrap(), answer, "{typed:?}"),
+                None => assert_eq!(
+                    result.unwrap_err().kind(),
+                    io::ErrorKind::UnexpectedEof,
+                    "{typed:?}"
+                ),
+            }
+            let output = String::from_utf8(output).unwrap();
+            assert_eq!(output.matches("Go? ").count(), asked, "{typed:?}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_first_error() {
+        assert!(FirstError::default().into_result().is_ok());
+
+        let mut first_error = FirstError::default();
+        first_error.record("install A", ApiError::InvalidFileHash);
+        first_error.record("install B", ApiError::NoDownloadUrl);
+        assert!(matches!(
+            first_error.into_result(),
+            Err(ApiError::InvalidFileHash)
+        ));
+    }
+}